@@ -0,0 +1,246 @@
+use std::{
+    future::Future,
+    mem,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+    task::{Context, Poll, Wake, Waker},
+};
+
+use pyo3::{exceptions::PyRuntimeError, intern, prelude::*};
+
+type BoxFuture = Pin<Box<dyn Future<Output = PyResult<PyObject>> + Send + 'static>>;
+
+enum AwaitableState {
+    Pending(BoxFuture),
+    Ready(PyResult<PyObject>),
+    /// The result has been taken by [RustAwaitable::result]
+    Consumed,
+    Cancelled,
+}
+
+/// A Rust [Future] that can be awaited from Python (`asyncio` or `trio`).
+///
+/// The future is polled on the Python event loop thread (with the GIL released),
+/// and its [Waker] schedules the next poll into the event loop thread-safely,
+/// so no thread is spawned for each future. It requires the `pyfuture` Python package,
+/// see `pyfuture._await_rust_future`.
+///
+/// If the Python task awaiting it is cancelled, the Rust future is dropped.
+///
+/// # NOTE
+///
+/// - The future must not block when polled, because it's polled on the event loop thread.
+/// - The future is not polled in any Rust async runtime context, so the futures that
+///   require a specific runtime (e.g., `tokio::time::sleep`) should be spawned on that runtime,
+///   and await its `JoinHandle` instead.
+/// - The [Waker] acquires the GIL to notify the event loop, so just like [RustFuture](crate::future::RustFuture),
+///   DO NOT wake it while holding any other lock that the Python side may wait for.
+#[pyclass(frozen)]
+pub struct RustAwaitable(Mutex<AwaitableState>);
+
+impl RustAwaitable {
+    pub fn new<F>(future: F) -> Self
+    where
+        F: Future<Output = PyResult<PyObject>> + Send + 'static,
+    {
+        Self(Mutex::new(AwaitableState::Pending(Box::pin(future))))
+    }
+
+    fn state(&self) -> MutexGuard<'_, AwaitableState> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Calls the Python `wake` callback only once.
+struct PyWaker {
+    wake: PyObject,
+    woken: AtomicBool,
+}
+
+impl Wake for PyWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        if self.woken.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        Python::with_gil(|py| {
+            if let Err(e) = self.wake.call0(py) {
+                e.write_unraisable(py, Some(self.wake.bind(py)));
+            }
+        });
+    }
+}
+
+#[pymethods]
+impl RustAwaitable {
+    fn __await__<'py>(slf: &Bound<'py, Self>) -> PyResult<Bound<'py, PyAny>> {
+        let py = slf.py();
+        py.import("pyfuture")?
+            .call_method1(intern!(py, "_await_rust_future"), (slf,))?
+            .call_method0(intern!(py, "__await__"))
+    }
+
+    /// Polls the Rust future, returns `True` if it is done (or cancelled).
+    ///
+    /// Otherwise, `wake` will be called (once, on any thread) when it should be polled again.
+    fn poll(&self, py: Python<'_>, wake: PyObject) -> bool {
+        let waker = Waker::from(Arc::new(PyWaker {
+            wake,
+            woken: AtomicBool::new(false),
+        }));
+        // NOTE: release the GIL, the future may need the GIL on other threads
+        // (e.g., [RustFuture](crate::future::RustFuture))
+        py.allow_threads(|| {
+            let mut state = self.state();
+            let AwaitableState::Pending(future) = &mut *state else {
+                return true;
+            };
+            match future.as_mut().poll(&mut Context::from_waker(&waker)) {
+                Poll::Pending => false,
+                Poll::Ready(result) => {
+                    *state = AwaitableState::Ready(result);
+                    true
+                }
+            }
+        })
+    }
+
+    /// Takes the result of the Rust future, call it after [RustAwaitable::poll] returns `True`.
+    fn result(&self, py: Python<'_>) -> PyResult<PyObject> {
+        // NOTE: DO NOT lock the state with the GIL held, see [RustAwaitable::poll]
+        let result = py.allow_threads(|| {
+            let mut state = self.state();
+            match mem::replace(&mut *state, AwaitableState::Consumed) {
+                AwaitableState::Ready(result) => Ok(result),
+                AwaitableState::Pending(future) => {
+                    *state = AwaitableState::Pending(future);
+                    Err("The Rust future is not done yet")
+                }
+                AwaitableState::Consumed => Err("The Rust future result has already been taken"),
+                AwaitableState::Cancelled => {
+                    *state = AwaitableState::Cancelled;
+                    Err("The Rust future has been cancelled")
+                }
+            }
+        });
+        result.map_err(PyRuntimeError::new_err)?
+    }
+
+    /// Drops the Rust future if it is not done yet.
+    fn cancel(&self, py: Python<'_>) {
+        // NOTE: drop the future without the GIL, the `Drop` of it may need the GIL
+        // (e.g., [CancelOnDrop](crate::future::CancelOnDrop))
+        py.allow_threads(|| {
+            let mut state = self.state();
+            if let AwaitableState::Pending(_) = &*state {
+                let future = mem::replace(&mut *state, AwaitableState::Cancelled);
+                drop(state);
+                drop(future);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        future::{pending, poll_fn},
+        slice,
+        sync::mpsc,
+        thread,
+    };
+
+    use pyo3::IntoPyObjectExt as _;
+
+    use super::*;
+    use crate::test_utils::with_runner;
+
+    /// Sets the flag when dropped.
+    struct DropFlag(Arc<AtomicBool>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::Release);
+        }
+    }
+
+    fn assert_runtime_error(py: Python<'_>, result: PyResult<Bound<'_, PyAny>>, msg: &str) {
+        let err = result.unwrap_err();
+        assert!(err.is_instance_of::<PyRuntimeError>(py), "{err}");
+        assert_eq!(err.value(py).to_string(), msg);
+    }
+
+    #[test]
+    fn test_await_woken_from_other_thread() {
+        with_runner(|test_runner| {
+            let (waker_tx, waker_rx) = mpsc::channel::<Waker>();
+            let waker_thread = thread::spawn(move || waker_rx.recv().unwrap().wake());
+
+            let mut polled = 0;
+            let awaitable = RustAwaitable::new(poll_fn(move |cx| {
+                polled += 1;
+                if polled == 1 {
+                    waker_tx.send(cx.waker().clone()).unwrap();
+                    return Poll::Pending;
+                }
+                Poll::Ready(Python::with_gil(|py| polled.into_py_any(py)))
+            }));
+
+            Python::with_gil(|py| {
+                let awaitable = Bound::new(py, awaitable).unwrap().into_any();
+                let result = test_runner
+                    .call_async(py, "await_", slice::from_ref(&awaitable))
+                    .unwrap();
+                // polled again only after being woken
+                assert_eq!(result.extract::<i32>().unwrap(), 2);
+
+                assert_runtime_error(
+                    py,
+                    awaitable.call_method0("result"),
+                    "The Rust future result has already been taken",
+                );
+            });
+            waker_thread.join().unwrap();
+        });
+    }
+
+    #[test]
+    fn test_cancel_drops_future() {
+        with_runner(|test_runner| {
+            let dropped = Arc::new(AtomicBool::new(false));
+            let drop_flag = DropFlag(dropped.clone());
+            let awaitable = RustAwaitable::new(async move {
+                let _drop_flag = drop_flag;
+                pending().await
+            });
+
+            Python::with_gil(|py| {
+                let awaitable = Bound::new(py, awaitable).unwrap().into_any();
+                assert_runtime_error(
+                    py,
+                    awaitable.call_method0("result"),
+                    "The Rust future is not done yet",
+                );
+
+                let timeout = 0.1.into_bound_py_any(py).unwrap();
+                let cancelled = test_runner
+                    .call_async(py, "await_cancelled", &[awaitable.clone(), timeout])
+                    .unwrap();
+                assert!(cancelled.extract::<bool>().unwrap());
+                assert!(dropped.load(Ordering::Acquire));
+
+                assert_runtime_error(
+                    py,
+                    awaitable.call_method0("result"),
+                    "The Rust future has been cancelled",
+                );
+            });
+        });
+    }
+}
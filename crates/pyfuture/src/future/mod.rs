@@ -10,7 +10,7 @@ use std::{
 use pyo3::prelude::*;
 
 pub use py::PyFuture;
-pub use rust::{CancelOnDrop, RustFuture};
+pub use rust::{CancelOnDrop, CancelOnDropInBackground, Cancellable, RustFuture};
 
 #[derive(Debug)]
 pub struct AllowThreads<F>(pub F);
@@ -1,52 +1,79 @@
-use std::task::Waker;
+use std::{
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    task::Waker,
+};
 
 use pyo3::prelude::*;
 
-#[pyclass(subclass)]
-pub struct PyFuture {
-    #[pyo3(get)]
-    awaitable: PyObject,
+#[derive(Debug)]
+struct Slot {
     waker: Waker,
     result: Option<PyResult<PyObject>>,
 }
 
-impl PyFuture {
-    pub(crate) const fn new(awaitable: PyObject, waker: Waker) -> Self {
-        Self {
-            awaitable,
-            waker,
-            result: None,
-        }
+/// The result slot shared by [PyFuture] and [RustFuture](crate::future::RustFuture),
+/// so the Rust side can poll the result without the GIL.
+///
+/// NOTE: DO NOT call into Python while holding the lock.
+#[derive(Debug)]
+pub(crate) struct SharedSlot(Mutex<Slot>);
+
+impl SharedSlot {
+    fn lock(&self) -> MutexGuard<'_, Slot> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
     }
 
-    fn wake(&self) {
-        self.waker.wake_by_ref();
+    fn set(&self, result: PyResult<PyObject>) {
+        let waker = {
+            let mut slot = self.lock();
+            slot.result = Some(result);
+            slot.waker.clone()
+        };
+        // wake outside the lock, the waker may poll the future immediately
+        waker.wake();
     }
 
-    pub(crate) fn waker_clone_from(&mut self, waker: &Waker) {
-        self.waker.clone_from(waker);
+    /// Takes the result if it has been set, otherwise registers `waker` to be woken.
+    pub(crate) fn poll(&self, waker: &Waker) -> Option<PyResult<PyObject>> {
+        let mut slot = self.lock();
+        let result = slot.result.take();
+        if result.is_none() {
+            slot.waker.clone_from(waker);
+        }
+        result
     }
+}
 
-    pub(crate) fn result_as_ref(&self) -> Option<&PyResult<PyObject>> {
-        self.result.as_ref()
+#[pyclass(subclass, frozen)]
+pub struct PyFuture {
+    #[pyo3(get)]
+    awaitable: PyObject,
+    slot: Arc<SharedSlot>,
+}
+
+impl PyFuture {
+    pub(crate) fn new(awaitable: PyObject, waker: Waker) -> Self {
+        Self {
+            awaitable,
+            slot: Arc::new(SharedSlot(Mutex::new(Slot {
+                waker,
+                result: None,
+            }))),
+        }
     }
 
-    // // we don't need yet, just leave it here for future use
-    //
-    // pub(crate) fn result_as_mut(&mut self) -> Option<&mut PyResult<PyObject>> {
-    //     self.result.as_mut()
-    // }
+    pub(crate) fn slot(&self) -> Arc<SharedSlot> {
+        self.slot.clone()
+    }
 }
 
 #[pymethods]
 impl PyFuture {
-    fn set_result(&mut self, result: PyObject) {
-        self.result = Some(Ok(result));
-        self.wake();
+    fn set_result(&self, result: PyObject) {
+        self.slot.set(Ok(result));
     }
 
-    fn set_exception(&mut self, exception: Bound<'_, PyAny>) {
-        self.result = Some(Err(PyErr::from_value(exception)));
-        self.wake();
+    fn set_exception(&self, exception: Bound<'_, PyAny>) {
+        self.slot.set(Err(PyErr::from_value(exception)));
     }
 }
@@ -1,16 +1,17 @@
-// TODO, XXX: `eprintln` and `println` is not async-safe and atomic,
-// use `log` crate instead.
-// See: <https://pyo3.rs/v0.22.5/ecosystem/logging>
-
 use std::{
     future::Future,
     pin::{pin, Pin},
+    sync::{
+        mpsc::{channel, Sender},
+        Arc, OnceLock,
+    },
     task::{Context, Poll},
+    thread,
 };
 
 use pyo3::prelude::*;
 
-use crate::future::py::PyFuture;
+use crate::future::py::{PyFuture, SharedSlot};
 
 #[derive(Debug)]
 struct InitRustFuture {
@@ -20,7 +21,7 @@ struct InitRustFuture {
 
 #[derive(Debug)]
 struct RunningRustFuture {
-    pub(self) py_future: Py<PyFuture>,
+    pub(self) slot: Arc<SharedSlot>,
     pub(self) cancel_handle: PyObject,
     pub(self) cancellation_required: bool,
 }
@@ -34,9 +35,12 @@ enum RustFutureInner {
 
 /// # NOTE
 ///
-/// When calling the [RustFuture::poll] method, it will internally call [Python::with_gil],
-/// which means it may block the Rust async runtime.
+/// When calling the [RustFuture::poll] method for the first time, it will internally call
+/// [Python::with_gil] to call the runner, which means it may block the Rust async runtime.
 /// Therefore, it is best to use a separate Rust async runtime to schedule this future.
+///
+/// The later polls don't require the GIL, because the Python side sets the result into
+/// a slot shared with this future.
 
 // The reason why we use Inner struct instead of directly use `enum RustFuture`:
 //
@@ -84,13 +88,29 @@ impl RustFuture {
     // you have to use `&mut` to make sure only one thread can cancel the future at a time,
     // it's for thread-safe for python async runtime.
     pub fn cancel(&mut self, py: Python<'_>) -> PyResult<PyObject> {
+        self.cancel_with_reason(py, None)
+    }
+
+    /// Same as [RustFuture::cancel], but the `reason` is passed to the cancel handle of the runner,
+    /// which will resolve this future with a new cancellation exception whose argument is `reason`
+    /// (e.g., `asyncio.CancelledError(reason)`). The exception propagating in Python is not modified.
+    pub fn cancel_with_reason(
+        &mut self,
+        py: Python<'_>,
+        reason: Option<&str>,
+    ) -> PyResult<PyObject> {
         match &mut self.0 {
             RustFutureInner::Running(RunningRustFuture {
                 cancel_handle,
                 cancellation_required,
                 ..
             }) => {
-                let result = cancel_handle.call0(py)?;
+                // NOTE: don't pass `None` to keep compatible with the runners
+                // whose cancel handle doesn't accept the `reason`
+                let result = match reason {
+                    Some(reason) => cancel_handle.call1(py, (reason,))?,
+                    None => cancel_handle.call0(py)?,
+                };
                 *cancellation_required = true;
                 Ok(result)
             }
@@ -109,8 +129,7 @@ impl RustFuture {
 impl Drop for RustFuture {
     fn drop(&mut self) {
         if self.is_running() && !self.is_cancellation_required() {
-            // TODO: use `log` crate, for recoding line number and file name
-            eprintln!("[Warning] {self:?}: RustFuture dropped when PyFuture maybe still running");
+            log::warn!("{self:?}: RustFuture dropped when PyFuture maybe still running");
         }
     }
 }
@@ -152,7 +171,7 @@ impl Future for RustFuture {
                         });
 
                     RunningRustFuture {
-                        py_future: py_future.unbind(),
+                        slot: py_future.get().slot(),
                         cancel_handle,
                         cancellation_required: false,
                     }
@@ -161,64 +180,240 @@ impl Future for RustFuture {
                 Poll::Pending
             }
             RustFutureInner::Running(running_rust_future) => {
-                let RunningRustFuture { py_future, .. } = running_rust_future;
-                let result = Python::with_gil(|py| {
-                    let mut py_future = py_future.borrow_mut(py);
-                    match py_future.result_as_ref() {
-                        None => {
-                            py_future.waker_clone_from(cx.waker());
-                            Poll::Pending
-                        }
-                        Some(result) => {
-                            let result = result
-                                .as_ref()
-                                .map(|ok| ok.clone_ref(py))
-                                .map_err(|err| err.clone_ref(py));
-                            Poll::Ready(result)
-                        }
+                let RunningRustFuture { slot, .. } = running_rust_future;
+                // NOTE: no GIL here, see [SharedSlot]
+                match slot.poll(cx.waker()) {
+                    None => Poll::Pending,
+                    Some(result) => {
+                        *inner = RustFutureInner::Done;
+                        Poll::Ready(result)
                     }
-                });
-                if result.is_ready() {
-                    *inner = RustFutureInner::Done;
                 }
-                result
             }
             RustFutureInner::Done => panic!("Polling a done future"),
         }
     }
 }
 
+/// A [RustFuture] or [RustStream](crate::stream::RustStream) that can be cancelled, see [CancelOnDrop].
+pub trait Cancellable {
+    /// Whether the Python side is running and has not been cancelled yet.
+    fn is_cancellation_needed(&self) -> bool;
+
+    /// Cancels the Python side, see [RustFuture::cancel_with_reason].
+    fn cancel(&mut self, py: Python<'_>, reason: Option<&str>) -> PyResult<PyObject>;
+}
+
+impl Cancellable for RustFuture {
+    fn is_cancellation_needed(&self) -> bool {
+        self.is_running() && !self.is_cancellation_required()
+    }
+
+    fn cancel(&mut self, py: Python<'_>, reason: Option<&str>) -> PyResult<PyObject> {
+        self.cancel_with_reason(py, reason)
+    }
+}
+
 #[derive(Debug)]
-pub struct CancelOnDrop(pub RustFuture);
+pub struct CancelOnDrop<T: Cancellable = RustFuture>(pub T);
 
-impl Drop for CancelOnDrop {
+impl<T: Cancellable> Drop for CancelOnDrop<T> {
     fn drop(&mut self) {
         // perf: maybe we can use `ManuallyDrop` to avoid the cost of `self.0.drop`?
         // But `ManuallyDrop` will require `unsafe` block, i don't like any `unsafe` block.
         let rs_future = &mut self.0;
-        if rs_future.is_running() && !rs_future.is_cancellation_required() {
-            Python::with_gil(|py| {
-                let result = rs_future.cancel(py);
-                if let Err(e) = result {
-                    match e.traceback(py).map(|t| t.format()) {
-                        // TODO: use `log` crate instead of `eprintln!`
-                        Some(Ok(traceback)) => {
-                            eprintln!("[Warning] Error while cancelling on drop: {e}\n{traceback}");
-                        }
-                        _ => {
-                            eprintln!("Warning] Error while cancelling on drop: {e:?}");
-                        }
-                    }
+        if rs_future.is_cancellation_needed() {
+            Python::with_gil(|py| cancel_on_drop(py, rs_future));
+        }
+    }
+}
+
+fn cancel_on_drop(py: Python<'_>, rs_future: &mut impl Cancellable) {
+    let result = rs_future.cancel(py, None);
+    if let Err(e) = result {
+        match e.traceback(py).map(|t| t.format()) {
+            Some(Ok(traceback)) => {
+                log::warn!("Error while cancelling on drop: {e}\n{traceback}");
+            }
+            _ => {
+                log::warn!("Error while cancelling on drop: {e:?}");
+            }
+        }
+    }
+}
+
+/// Same as [CancelOnDrop], but the cancellation is sent to a dedicated background thread
+/// (`pyfuture-canceller`), so [Drop] never blocks on the GIL.
+///
+/// The Python side will be cancelled a little later than dropping, and the inner future
+/// is dropped on the background thread after being cancelled.
+#[derive(Debug)]
+pub struct CancelOnDropInBackground<T: Cancellable + Send + 'static = RustFuture>(Option<T>);
+
+impl<T: Cancellable + Send + 'static> CancelOnDropInBackground<T> {
+    pub const fn new(inner: T) -> Self {
+        Self(Some(inner))
+    }
+
+    pub fn into_inner(mut self) -> T {
+        self.0
+            .take()
+            .expect("the inner is only taken in `into_inner` or `drop`")
+    }
+
+    pub fn inner_mut(&mut self) -> &mut T {
+        self.0
+            .as_mut()
+            .expect("the inner is only taken in `into_inner` or `drop`")
+    }
+}
+
+type BackgroundCancel = Box<dyn FnOnce(Python<'_>) + Send>;
+
+fn background_canceller() -> &'static Sender<BackgroundCancel> {
+    static CANCELLER: OnceLock<Sender<BackgroundCancel>> = OnceLock::new();
+    CANCELLER.get_or_init(|| {
+        let (sender, receiver) = channel::<BackgroundCancel>();
+        thread::Builder::new()
+            .name("pyfuture-canceller".to_owned())
+            .spawn(move || {
+                for cancel in receiver {
+                    Python::with_gil(cancel);
                 }
-            });
+            })
+            .expect("Failed to spawn the `pyfuture-canceller` thread");
+        sender
+    })
+}
+
+impl<T: Cancellable + Send + 'static> Drop for CancelOnDropInBackground<T> {
+    fn drop(&mut self) {
+        if let Some(mut rs_future) = self.0.take() {
+            if rs_future.is_cancellation_needed() {
+                // the receiver never exits, so this never fails
+                let _ = background_canceller()
+                    .send(Box::new(move |py| cancel_on_drop(py, &mut rs_future)));
+            }
         }
     }
 }
 
-impl Future for CancelOnDrop {
+impl Future for CancelOnDropInBackground<RustFuture> {
+    type Output = PyResult<PyObject>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        pin!(self.inner_mut()).poll(cx)
+    }
+}
+
+impl Future for CancelOnDrop<RustFuture> {
     type Output = PyResult<PyObject>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         pin!(&mut self.0).poll(&mut Context::from_waker(cx.waker()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pyo3::{exceptions::asyncio::CancelledError, types::PyList};
+
+    use super::*;
+    use crate::test_utils::{block_on, poll_once, wait_until, with_runner, TestRunner};
+
+    /// Returns the future of `sleep_until_cancelled`, and the list of the args of its `CancelledError`.
+    fn sleep_until_cancelled(test_runner: &TestRunner) -> (RustFuture, Py<PyList>) {
+        Python::with_gil(|py| {
+            let cancelled = PyList::empty(py);
+            let awaitable = test_runner.call(py, "sleep_until_cancelled", (&cancelled,));
+            let future = test_runner.runner.borrow(py).future(py, awaitable);
+            (future, cancelled.unbind())
+        })
+    }
+
+    /// Starts running `future` on the Python side.
+    fn start(future: &mut RustFuture) {
+        assert!(poll_once(|cx| pin!(&mut *future).poll(cx)).is_pending());
+        assert!(future.is_running());
+    }
+
+    /// Cancels the running `future` and returns the args of the `CancelledError` it's resolved with.
+    fn cancel_args(mut future: RustFuture, reason: Option<&str>) -> Vec<String> {
+        start(&mut future);
+        Python::with_gil(|py| future.cancel_with_reason(py, reason)).unwrap();
+        assert!(future.is_cancellation_required());
+
+        let err = block_on(future).unwrap_err();
+        Python::with_gil(|py| {
+            assert!(err.is_instance_of::<CancelledError>(py), "{err}");
+            err.value(py).getattr("args").unwrap().extract().unwrap()
+        })
+    }
+
+    #[test]
+    fn test_cancel() {
+        with_runner(|test_runner| {
+            let (future, cancelled) = sleep_until_cancelled(test_runner);
+            // NOTE: without `reason`, the args are decided by the async library
+            cancel_args(future, None);
+            // the Python side is cancelled indeed
+            wait_until(|| Python::with_gil(|py| cancelled.bind(py).len() == 1));
+        });
+    }
+
+    #[test]
+    fn test_cancel_with_reason() {
+        with_runner(|test_runner| {
+            let (future, cancelled) = sleep_until_cancelled(test_runner);
+            assert_eq!(cancel_args(future, Some("shutdown")), ["shutdown"]);
+            wait_until(|| Python::with_gil(|py| cancelled.bind(py).len() == 1));
+        });
+    }
+
+    /// The future is started on the event loop thread, so it's cancelled by the `CancelScope`.
+    #[test]
+    fn test_cancel_with_reason_on_event_loop() {
+        with_runner(|test_runner| {
+            let (mut future, cancelled) = sleep_until_cancelled(test_runner);
+            let runner = Python::with_gil(|py| test_runner.runner.clone_ref(py));
+            let (mut future, other) = test_runner.on_event_loop(move |py| {
+                start(&mut future);
+                let awaitable = py
+                    .import("asyncio")
+                    .unwrap()
+                    .call_method1("sleep", (0.5, "done"))
+                    .unwrap()
+                    .unbind();
+                let mut other = runner.borrow(py).future(py, awaitable);
+                start(&mut other);
+                (future, other)
+            });
+
+            Python::with_gil(|py| future.cancel_with_reason(py, Some("shutdown"))).unwrap();
+            let err = block_on(future).unwrap_err();
+            Python::with_gil(|py| {
+                assert!(err.is_instance_of::<CancelledError>(py), "{err}");
+                let args: Vec<String> = err.value(py).getattr("args").unwrap().extract().unwrap();
+                assert_eq!(args, ["shutdown"]);
+            });
+            wait_until(|| Python::with_gil(|py| cancelled.bind(py).len() == 1));
+
+            // the `CancelScope` suppresses the cancellation,
+            // so the other futures in the same `TaskGroup` are not cancelled
+            let result = block_on(other).unwrap();
+            Python::with_gil(|py| assert_eq!(result.extract::<String>(py).unwrap(), "done"));
+        });
+    }
+
+    #[test]
+    fn test_cancel_on_drop_in_background() {
+        with_runner(|test_runner| {
+            let (mut future, cancelled) = sleep_until_cancelled(test_runner);
+            start(&mut future);
+
+            drop(CancelOnDropInBackground::new(future));
+            wait_until(|| Python::with_gil(|py| cancelled.bind(py).len() == 1));
+            assert_eq!(test_runner.cancel_threads(), ["pyfuture-canceller"]);
+        });
+    }
+}
@@ -5,5 +5,10 @@
     doc(cfg_hide(doc))
 )]
 
+pub mod awaitable;
 pub mod future;
 pub mod runner;
+pub mod stream;
+
+#[cfg(test)]
+mod test_utils;
@@ -1,6 +1,9 @@
-use pyo3::prelude::*;
+use std::time::Duration;
+
+use pyo3::{intern, prelude::*};
 
 use crate::future::RustFuture;
+use crate::stream::RustStream;
 
 #[cfg(not(feature = "sync"))]
 use std::marker::PhantomData;
@@ -86,6 +89,57 @@ impl Runner {
             .expect("The runner is already closed")
     }
 
+    /// Same as [Runner::try_future], but the Python side will be cancelled when `timeout` passes,
+    /// then the [RustFuture] will be resolved with `TimeoutError`.
+    ///
+    /// It requires the `pyfuture` Python package, see `pyfuture._with_timeout`.
+    pub fn try_future_with_timeout(
+        &self,
+        py: Python<'_>,
+        awaitable: PyObject,
+        timeout: Duration,
+    ) -> PyResult<Option<RustFuture>> {
+        if self.is_closed() {
+            return Ok(None);
+        }
+        let awaitable = py
+            .import("pyfuture")?
+            .call_method1(
+                intern!(py, "_with_timeout"),
+                (awaitable, timeout.as_secs_f64()),
+            )?
+            .unbind();
+        Ok(self.try_future(py, awaitable))
+    }
+
+    /// Same as [Runner::try_future_with_timeout], but panics if the runner is already closed,
+    /// just like [Runner::future].
+    pub fn future_with_timeout(
+        &self,
+        py: Python<'_>,
+        awaitable: PyObject,
+        timeout: Duration,
+    ) -> PyResult<RustFuture> {
+        self.try_future_with_timeout(py, awaitable, timeout)
+            .map(|future| future.expect("The runner is already closed"))
+    }
+
+    /// `async_iterator` is a Python async iterator, e.g., an async generator,
+    /// see [RustStream].
+    pub fn try_stream(&self, py: Python<'_>, async_iterator: PyObject) -> Option<RustStream> {
+        match &self.0 {
+            RunnerInner::Alive { runner, .. } => {
+                let runner = runner.inner.clone_ref(py);
+                Some(RustStream::new(runner, async_iterator))
+            }
+            RunnerInner::Closed => None,
+        }
+    }
+    pub fn stream(&self, py: Python<'_>, async_iterator: PyObject) -> RustStream {
+        self.try_stream(py, async_iterator)
+            .expect("The runner is already closed")
+    }
+
     pub fn is_closed(&self) -> bool {
         matches!(self.0, RunnerInner::Closed)
     }
@@ -137,3 +191,60 @@ mod notification {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pyo3::exceptions::PyTimeoutError;
+
+    use super::*;
+    use crate::test_utils::{block_on, with_runner};
+
+    #[test]
+    fn test_future_with_timeout() {
+        with_runner(|test_runner| {
+            let future = Python::with_gil(|py| {
+                let awaitable = test_runner.call(py, "sleep_until_cancelled", (Vec::<()>::new(),));
+                test_runner
+                    .runner
+                    .borrow(py)
+                    .future_with_timeout(py, awaitable, Duration::from_millis(50))
+                    .unwrap()
+            });
+            let err = block_on(future).unwrap_err();
+            Python::with_gil(|py| assert!(err.is_instance_of::<PyTimeoutError>(py), "{err}"));
+        });
+    }
+
+    #[test]
+    fn test_future_within_timeout() {
+        with_runner(|test_runner| {
+            let future = Python::with_gil(|py| {
+                let awaitable =
+                    test_runner.call(py, "sleep_until_cancelled", (Vec::<()>::new(), 0.01));
+                test_runner
+                    .runner
+                    .borrow(py)
+                    .future_with_timeout(py, awaitable, Duration::from_secs(10))
+                    .unwrap()
+            });
+            let result = block_on(future).unwrap();
+            Python::with_gil(|py| assert!(result.is_none(py)));
+        });
+    }
+
+    #[test]
+    fn test_closed_runner() {
+        with_runner(|test_runner| {
+            Python::with_gil(|py| {
+                let mut runner = test_runner.runner.borrow_mut(py);
+                runner.close();
+                assert!(runner.is_closed());
+                let awaitable = test_runner.call(py, "sleep_until_cancelled", (Vec::<()>::new(),));
+                assert!(runner
+                    .try_future_with_timeout(py, awaitable, Duration::from_secs(1))
+                    .unwrap()
+                    .is_none());
+            });
+        });
+    }
+}
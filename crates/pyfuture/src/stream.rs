@@ -0,0 +1,250 @@
+use std::{
+    future::Future as _,
+    pin::{pin, Pin},
+    task::{Context, Poll},
+};
+
+use futures_core::{FusedStream, Stream};
+use pyo3::{exceptions::PyStopAsyncIteration, intern, prelude::*};
+
+use crate::future::{CancelOnDrop, CancelOnDropInBackground, Cancellable, RustFuture};
+
+#[derive(Debug)]
+struct RunningRustStream {
+    pub(self) async_iterator: PyObject,
+    pub(self) runner: PyObject,
+    /// The [RustFuture] of the pending `__anext__()`
+    pub(self) next: Option<RustFuture>,
+}
+
+#[derive(Debug)]
+enum RustStreamInner {
+    Running(RunningRustStream),
+    Done,
+}
+
+/// A Python async iterator (e.g., an async generator) as a [Stream],
+/// each `__anext__()` is run by the runner just like [RustFuture].
+///
+/// The stream ends when the async iterator raises `StopAsyncIteration`,
+/// or after yielding the first other exception.
+///
+/// # NOTE
+///
+/// Just like [RustFuture], [RustStream::poll_next] will internally call [Python::with_gil],
+/// so it is best to use a separate Rust async runtime to schedule this stream.
+/// And use [CancelOnDrop] to cancel the pending `__anext__()` when dropped.
+#[derive(Debug)]
+pub struct RustStream(RustStreamInner);
+
+impl RustStream {
+    pub(crate) const fn new(runner: PyObject, async_iterator: PyObject) -> Self {
+        Self(RustStreamInner::Running(RunningRustStream {
+            async_iterator,
+            runner,
+            next: None,
+        }))
+    }
+
+    #[inline]
+    pub fn is_done(&self) -> bool {
+        matches!(&self.0, RustStreamInner::Done)
+    }
+
+    /// Cancels the pending `__anext__()` (see [RustFuture::cancel_with_reason]) and ends the stream.
+    ///
+    /// Returns `None` if there is no pending `__anext__()`.
+    pub fn cancel(&mut self, py: Python<'_>, reason: Option<&str>) -> PyResult<Option<PyObject>> {
+        let inner = std::mem::replace(&mut self.0, RustStreamInner::Done);
+        match inner {
+            RustStreamInner::Running(RunningRustStream {
+                next: Some(mut next),
+                ..
+            }) if next.is_running() => next.cancel_with_reason(py, reason).map(Some),
+            _ => Ok(None),
+        }
+    }
+}
+
+impl Cancellable for RustStream {
+    fn is_cancellation_needed(&self) -> bool {
+        match &self.0 {
+            RustStreamInner::Running(RunningRustStream {
+                next: Some(next), ..
+            }) => next.is_cancellation_needed(),
+            _ => false,
+        }
+    }
+
+    fn cancel(&mut self, py: Python<'_>, reason: Option<&str>) -> PyResult<PyObject> {
+        RustStream::cancel(self, py, reason).map(|result| result.unwrap_or_else(|| py.None()))
+    }
+}
+
+impl Stream for RustStream {
+    type Item = PyResult<PyObject>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let inner = &mut self.get_mut().0;
+        let RustStreamInner::Running(RunningRustStream {
+            async_iterator,
+            runner,
+            next,
+        }) = inner
+        else {
+            return Poll::Ready(None);
+        };
+
+        let next_future = match next {
+            Some(next_future) => next_future,
+            None => {
+                // NOTE: DO NOT use any other lock in GIL, see [RustFuture::poll].
+                let awaitable = Python::with_gil(|py| {
+                    async_iterator
+                        .call_method0(py, intern!(py, "__anext__"))
+                        .map(|awaitable| (runner.clone_ref(py), awaitable))
+                });
+                match awaitable {
+                    Ok((runner, awaitable)) => next.insert(RustFuture::new(runner, awaitable)),
+                    Err(e) => {
+                        *inner = RustStreamInner::Done;
+                        return Poll::Ready(end_of_stream(e));
+                    }
+                }
+            }
+        };
+
+        match pin!(next_future).poll(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(Ok(item)) => {
+                *next = None;
+                Poll::Ready(Some(Ok(item)))
+            }
+            Poll::Ready(Err(e)) => {
+                *inner = RustStreamInner::Done;
+                Poll::Ready(end_of_stream(e))
+            }
+        }
+    }
+}
+
+impl FusedStream for RustStream {
+    fn is_terminated(&self) -> bool {
+        self.is_done()
+    }
+}
+
+/// `StopAsyncIteration` ends the stream, other exceptions are yielded.
+fn end_of_stream(err: PyErr) -> Option<PyResult<PyObject>> {
+    let is_stop = Python::with_gil(|py| err.is_instance_of::<PyStopAsyncIteration>(py));
+    if is_stop {
+        None
+    } else {
+        Some(Err(err))
+    }
+}
+
+impl Stream for CancelOnDrop<RustStream> {
+    type Item = PyResult<PyObject>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        pin!(&mut self.0).poll_next(cx)
+    }
+}
+
+impl Stream for CancelOnDropInBackground<RustStream> {
+    type Item = PyResult<PyObject>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        pin!(self.inner_mut()).poll_next(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{future::poll_fn, pin::Pin};
+
+    use pyo3::{call::PyCallArgs, exceptions::PyValueError, types::PyList};
+
+    use super::*;
+    use crate::test_utils::{block_on, poll_once, wait_until, with_runner, TestRunner};
+
+    fn stream(
+        test_runner: &TestRunner,
+        name: &str,
+        args: impl for<'py> PyCallArgs<'py>,
+    ) -> RustStream {
+        Python::with_gil(|py| {
+            let async_iterator = test_runner.call(py, name, args);
+            test_runner.runner.borrow(py).stream(py, async_iterator)
+        })
+    }
+
+    fn next<S: Stream + Unpin>(stream: &mut S) -> Option<S::Item> {
+        block_on(poll_fn(|cx| Pin::new(&mut *stream).poll_next(cx)))
+    }
+
+    fn extract_item(item: Option<PyResult<PyObject>>) -> i32 {
+        let item = item.expect("the stream ended").unwrap();
+        Python::with_gil(|py| item.extract(py).unwrap())
+    }
+
+    #[test]
+    fn test_iteration() {
+        with_runner(|test_runner| {
+            let mut stream = stream(test_runner, "async_gen", (vec![1, 2, 3],));
+            for expected in [1, 2, 3] {
+                assert_eq!(extract_item(next(&mut stream)), expected);
+            }
+            // `StopAsyncIteration`
+            assert!(next(&mut stream).is_none());
+            assert!(stream.is_terminated());
+        });
+    }
+
+    #[test]
+    fn test_exception() {
+        with_runner(|test_runner| {
+            let error = Python::with_gil(|py| PyValueError::new_err("boom").into_value(py));
+            let mut stream = stream(test_runner, "async_gen", (vec![1], error));
+            assert_eq!(extract_item(next(&mut stream)), 1);
+
+            let err = next(&mut stream)
+                .expect("the exception should be yielded")
+                .unwrap_err();
+            Python::with_gil(|py| assert!(err.is_instance_of::<PyValueError>(py)));
+            // the stream ends after the exception
+            assert!(stream.is_terminated());
+            assert!(next(&mut stream).is_none());
+        });
+    }
+
+    #[test]
+    fn test_fused() {
+        with_runner(|test_runner| {
+            let mut stream = stream(test_runner, "async_gen", (Vec::<i32>::new(),));
+            assert!(!stream.is_terminated());
+            assert!(next(&mut stream).is_none());
+            assert!(stream.is_terminated());
+            // polling a terminated stream doesn't panic
+            assert!(next(&mut stream).is_none());
+            assert!(next(&mut stream).is_none());
+        });
+    }
+
+    #[test]
+    fn test_cancel_on_drop() {
+        with_runner(|test_runner| {
+            let cancelled = Python::with_gil(|py| PyList::empty(py).unbind());
+            let args = Python::with_gil(|py| (cancelled.clone_ref(py),));
+            let mut stream = CancelOnDrop(stream(test_runner, "async_gen_until_cancelled", args));
+            assert_eq!(extract_item(next(&mut stream)), 1);
+            // start the pending `__anext__()`
+            assert!(poll_once(|cx| Pin::new(&mut stream).poll_next(cx)).is_pending());
+            assert!(stream.0.is_cancellation_needed());
+
+            drop(stream);
+            wait_until(|| Python::with_gil(|py| cancelled.bind(py).len() == 1));
+        });
+    }
+}
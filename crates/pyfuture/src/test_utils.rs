@@ -0,0 +1,233 @@
+//! The helpers for the tests which run the futures with the `pyfuture` Python package.
+//!
+//! The `pyfuture` package is imported from the source tree,
+//! but its dependencies (e.g., `anyio`) must be installed in the Python environment.
+
+use std::{
+    ffi::CStr,
+    future::{poll_fn, Future},
+    iter,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    thread,
+    time::{Duration, Instant},
+};
+
+use pyo3::{
+    call::PyCallArgs,
+    prelude::*,
+    types::{PyCFunction, PyList, PyTuple},
+};
+
+use crate::runner::Runner;
+
+const PYFUTURE_SRC: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../python/pyfuture/src");
+
+const HELPERS: &CStr = cr#"
+import asyncio
+import sys
+
+
+def runner_builder(pyfuture_src):
+    if pyfuture_src not in sys.path:
+        sys.path.insert(0, pyfuture_src)
+    from pyfuture import create_runner_builder
+
+    return create_runner_builder("asyncio")
+
+
+def recording_runner(runner_cls, cancel_threads):
+    """Records the thread names calling the cancel handles into `cancel_threads`."""
+
+    def build(py_runner):
+        def recording_py_runner(py_future):
+            cancel = py_runner(py_future)
+
+            def recording_cancel(*args):
+                # NOTE: `threading` doesn't know the names of the threads spawned by Rust
+                cancel_threads.append(current_thread_name())
+                cancel(*args)
+
+            return recording_cancel
+
+        return runner_cls(recording_py_runner)
+
+    return build
+
+
+async def sleep_until_cancelled(cancelled, seconds=60):
+    """Appends the args of `CancelledError` to `cancelled` when cancelled."""
+    try:
+        await asyncio.sleep(seconds)
+    except asyncio.CancelledError as e:
+        cancelled.append(e.args)
+        raise
+
+
+async def await_(awaitable):
+    return await awaitable
+
+
+async def await_cancelled(awaitable, seconds):
+    """Awaits `awaitable` and cancels it after `seconds`, returns whether it's cancelled."""
+    try:
+        await asyncio.wait_for(await_(awaitable), seconds)
+    except asyncio.TimeoutError:
+        return True
+    return False
+
+
+async def async_gen(items, error=None):
+    for item in items:
+        yield item
+    if error is not None:
+        raise error
+
+
+async def async_gen_until_cancelled(cancelled):
+    yield 1
+    await sleep_until_cancelled(cancelled)
+    yield 2
+"#;
+
+#[pyfunction]
+fn current_thread_name() -> Option<String> {
+    thread::current().name().map(ToOwned::to_owned)
+}
+
+/// See [with_runner].
+pub(crate) struct TestRunner {
+    pub(crate) runner: Py<Runner>,
+    /// The names of the threads calling the cancel handles of the runner.
+    pub(crate) cancel_threads: Py<PyList>,
+    pub(crate) helpers: Py<PyModule>,
+    /// The `anyio.from_thread.BlockingPortal` of the event loop thread.
+    pub(crate) portal: PyObject,
+}
+
+impl TestRunner {
+    /// Calls the helper function `name` defined in [HELPERS].
+    pub(crate) fn call<'py>(
+        &self,
+        py: Python<'py>,
+        name: &str,
+        args: impl PyCallArgs<'py>,
+    ) -> PyObject {
+        self.helpers
+            .bind(py)
+            .call_method1(name, args)
+            .unwrap()
+            .unbind()
+    }
+
+    pub(crate) fn cancel_threads(&self) -> Vec<String> {
+        Python::with_gil(|py| self.cancel_threads.bind(py).extract().unwrap())
+    }
+
+    /// Runs the async helper function `name` defined in [HELPERS] on the event loop thread,
+    /// and returns its result.
+    pub(crate) fn call_async<'py>(
+        &self,
+        py: Python<'py>,
+        name: &str,
+        args: &[Bound<'py, PyAny>],
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let func = self.helpers.bind(py).getattr(name)?;
+        let args = PyTuple::new(
+            py,
+            iter::once(func)
+                .chain(args.iter().cloned())
+                .collect::<Vec<_>>(),
+        )?;
+        self.portal.bind(py).call_method1("call", args)
+    }
+
+    /// Runs `f` on the event loop thread and returns its result.
+    pub(crate) fn on_event_loop<R: Send + 'static>(
+        &self,
+        f: impl FnOnce(Python<'_>) -> R + Send + 'static,
+    ) -> R {
+        let f = Mutex::new(Some(f));
+        let result = Arc::new(Mutex::new(None));
+        let result_clone = result.clone();
+        Python::with_gil(|py| {
+            let func = PyCFunction::new_closure(py, None, None, move |args, _kwargs| {
+                let f = f.lock().unwrap().take().expect("called more than once");
+                *result_clone.lock().unwrap() = Some(f(args.py()));
+            })
+            .unwrap();
+            self.portal.call_method1(py, "call", (func,)).unwrap();
+        });
+        let result = result.lock().unwrap().take();
+        result.expect("`f` was not called")
+    }
+}
+
+/// Runs `f` without the GIL, with a [Runner] built by `pyfuture.create_runner_builder`.
+pub(crate) fn with_runner<R: Send>(f: impl FnOnce(&TestRunner) -> R + Send) -> R {
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let helpers =
+            PyModule::from_code(py, HELPERS, c"pyfuture_test.py", c"pyfuture_test").unwrap();
+        helpers
+            .add_function(wrap_pyfunction!(current_thread_name, &helpers).unwrap())
+            .unwrap();
+        let context_manager = helpers
+            .call_method1("runner_builder", (PYFUTURE_SRC,))
+            .unwrap();
+        let builder = context_manager.call_method0("__enter__").unwrap();
+
+        let cancel_threads = PyList::empty(py);
+        let runner_cls = helpers
+            .call_method1(
+                "recording_runner",
+                (py.get_type::<Runner>(), &cancel_threads),
+            )
+            .unwrap();
+        let runner = builder
+            .call_method1("build", (runner_cls,))
+            .unwrap()
+            .downcast_into::<Runner>()
+            .unwrap()
+            .unbind();
+
+        let test_runner = TestRunner {
+            runner,
+            cancel_threads: cancel_threads.unbind(),
+            helpers: helpers.unbind(),
+            portal: builder.getattr("blocking_portal").unwrap().unbind(),
+        };
+        let result = py.allow_threads(|| f(&test_runner));
+
+        let py_none = py.None();
+        context_manager
+            .call_method1("__exit__", (&py_none, &py_none, &py_none))
+            .unwrap();
+        result
+    })
+}
+
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .unwrap()
+        .block_on(future)
+}
+
+/// Calls `poll` once, e.g., to start running a future on the Python side.
+pub(crate) fn poll_once<T>(mut poll: impl FnMut(&mut Context<'_>) -> Poll<T>) -> Poll<T> {
+    block_on(poll_fn(|cx| Poll::Ready(poll(cx))))
+}
+
+/// Waits until `condition` returns `true`, panics after 10 seconds.
+pub(crate) fn wait_until(mut condition: impl FnMut() -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !condition() {
+        assert!(
+            Instant::now() < deadline,
+            "timed out waiting for the condition"
+        );
+        thread::sleep(Duration::from_millis(10));
+    }
+}
@@ -0,0 +1,109 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{DeriveInput, LitStr, Type};
+
+use crate::parse::{struct_fields, ContainerOptions, FieldOptions};
+
+pub(crate) fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let DeriveInput {
+        attrs,
+        ident,
+        generics,
+        data,
+        ..
+    } = input;
+
+    let fields = struct_fields(data, "FromPyDict")?;
+
+    let ContainerOptions {
+        krate,
+        allow_unknown_fields,
+        ignore_keys,
+    } = ContainerOptions::from_attrs(&attrs)?;
+    let struct_name = ident.to_string();
+
+    // NOTE: the `cfg`-disabled fields have been removed by the compiler before expanding,
+    // so we don't need to handle the `#[cfg(...)]` attributes,
+    // but their keys are unknown unless they are listed in `ignore_keys`.
+    let mut known_keys = Vec::<LitStr>::new();
+    let mut field_inits = Vec::<TokenStream>::new();
+    for field in fields {
+        let FieldOptions {
+            default,
+            rename,
+            aliases,
+            validate,
+        } = FieldOptions::from_attrs(&field.attrs)?;
+        let field_ident = field.ident.expect("named fields always have ident");
+        let field_name = field_ident.to_string();
+        let field_ty: Type = field.ty;
+
+        let key = rename.unwrap_or_else(|| LitStr::new(&field_name, field_ident.span()));
+        let keys = std::iter::once(key).chain(aliases).collect::<Vec<_>>();
+        for key in &keys {
+            if known_keys.iter().any(|known| known.value() == key.value()) {
+                return Err(syn::Error::new(
+                    key.span(),
+                    format!("duplicate key `{}`", key.value()),
+                ));
+            }
+            known_keys.push(key.clone());
+        }
+
+        let extract_fn = if default {
+            quote!(__extract_field_with_default)
+        } else {
+            quote!(__extract_field)
+        };
+        let validate = validate.map(|validate| {
+            quote! {
+                #krate::from_py_dict::__validate_field(
+                    py,
+                    (#validate)(&value),
+                    #struct_name,
+                    #field_name,
+                )?;
+            }
+        });
+        field_inits.push(quote! {
+            #field_ident: {
+                let value = #krate::from_py_dict::#extract_fn::<#field_ty>(
+                    dict,
+                    &[#(::pyo3::intern!(py, #keys)),*],
+                    #struct_name,
+                    #field_name,
+                )?;
+                #validate
+                value
+            },
+        });
+    }
+
+    let check_unknown_keys = (!allow_unknown_fields).then(|| {
+        quote! {
+            #krate::from_py_dict::__check_unknown_keys(
+                dict,
+                &[#(#known_keys,)* #(#ignore_keys),*],
+                #struct_name,
+            )?;
+        }
+    });
+
+    // avoid the `unused_variables` warning for the structs without fields
+    let py = (!field_inits.is_empty()).then(|| quote!(let py = dict.py();));
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #krate::from_py_dict::FromPyDict for #ident #ty_generics #where_clause {
+            fn from_py_dict(
+                dict: &::pyo3::Bound<'_, ::pyo3::types::PyDict>,
+            ) -> ::pyo3::PyResult<Self> {
+                #py
+                #check_unknown_keys
+                ::core::result::Result::Ok(Self {
+                    #(#field_inits)*
+                })
+            }
+        }
+    })
+}
@@ -0,0 +1,73 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{DeriveInput, LitStr, Type};
+
+use crate::parse::{struct_fields, ContainerOptions, FieldOptions};
+
+/// Whether the type is `NotRequired<T>` (or `path::to::NotRequired<T>`).
+///
+/// NOTE: we can only check it syntactically, so the type aliases of `NotRequired` are not supported.
+fn is_not_required(ty: &Type) -> bool {
+    match ty {
+        Type::Path(ty) => ty
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "NotRequired"),
+        _ => false,
+    }
+}
+
+pub(crate) fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let DeriveInput {
+        attrs,
+        ident,
+        generics,
+        data,
+        ..
+    } = input;
+
+    let fields = struct_fields(data, "IntoPyDict")?;
+    // `allow_unknown_fields` and `ignore_keys` are only meaningful for `FromPyDict`
+    let ContainerOptions { krate, .. } = ContainerOptions::from_attrs(&attrs)?;
+
+    let set_items = fields
+        .into_iter()
+        .map(|field| {
+            // only the first key is used, the aliases are only for `FromPyDict`
+            let FieldOptions { rename, .. } = FieldOptions::from_attrs(&field.attrs)?;
+            let field_ident = field.ident.expect("named fields always have ident");
+            let key =
+                rename.unwrap_or_else(|| LitStr::new(&field_ident.to_string(), field_ident.span()));
+
+            let set_item = if is_not_required(&field.ty) {
+                quote! {
+                    self.#field_ident.__set_item_to(&dict, ::pyo3::intern!(py, #key))?;
+                }
+            } else {
+                quote! {
+                    ::pyo3::types::PyDictMethods::set_item(
+                        &dict,
+                        ::pyo3::intern!(py, #key),
+                        &self.#field_ident,
+                    )?;
+                }
+            };
+            Ok(set_item)
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #krate::from_py_dict::IntoPyDict for #ident #ty_generics #where_clause {
+            fn to_py_dict<'py>(
+                &self,
+                py: ::pyo3::Python<'py>,
+            ) -> ::pyo3::PyResult<::pyo3::Bound<'py, ::pyo3::types::PyDict>> {
+                let dict = ::pyo3::types::PyDict::new(py);
+                #(#set_items)*
+                ::core::result::Result::Ok(dict)
+            }
+        }
+    })
+}
@@ -0,0 +1,28 @@
+//! Procedural macros for [pyo3-utils](https://docs.rs/pyo3-utils).
+//!
+//! Use the re-exports in `pyo3-utils` instead of depending on this crate directly.
+
+mod from_py_dict;
+mod into_py_dict;
+mod parse;
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+/// See `pyo3_utils::from_py_dict::FromPyDict`.
+#[proc_macro_derive(FromPyDict, attributes(pyo3))]
+pub fn derive_from_py_dict(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    from_py_dict::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// See `pyo3_utils::from_py_dict::IntoPyDict`.
+#[proc_macro_derive(IntoPyDict, attributes(pyo3))]
+pub fn derive_into_py_dict(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    into_py_dict::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
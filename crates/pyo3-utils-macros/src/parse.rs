@@ -0,0 +1,108 @@
+use syn::{
+    punctuated::Punctuated, spanned::Spanned as _, token::Comma, Attribute, Data, Expr, Field,
+    Fields, LitStr, Path,
+};
+
+/// The `#[pyo3(...)]` options on the struct.
+pub(crate) struct ContainerOptions {
+    pub(crate) krate: Path,
+    pub(crate) allow_unknown_fields: bool,
+    /// The keys accepted but ignored, e.g., the keys of the `cfg`-disabled fields.
+    pub(crate) ignore_keys: Vec<LitStr>,
+}
+
+impl ContainerOptions {
+    pub(crate) fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut options = Self {
+            krate: syn::parse_quote!(::pyo3_utils),
+            allow_unknown_fields: false,
+            ignore_keys: Vec::new(),
+        };
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("pyo3")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("crate") {
+                    options.krate = meta.value()?.parse::<LitStr>()?.parse()?;
+                } else if meta.path.is_ident("allow_unknown_fields") {
+                    options.allow_unknown_fields = true;
+                } else if meta.path.is_ident("ignore_keys") {
+                    let content;
+                    syn::parenthesized!(content in meta.input);
+                    options
+                        .ignore_keys
+                        .extend(Punctuated::<LitStr, Comma>::parse_terminated(&content)?);
+                } else {
+                    return Err(meta.error(
+                        "expected `crate = \"...\"`, `allow_unknown_fields` or `ignore_keys(\"...\", ...)` for `#[derive(FromPyDict/IntoPyDict)]`",
+                    ));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(options)
+    }
+}
+
+/// The `#[pyo3(...)]` options on a field.
+#[derive(Default)]
+pub(crate) struct FieldOptions {
+    pub(crate) default: bool,
+    pub(crate) rename: Option<LitStr>,
+    pub(crate) aliases: Vec<LitStr>,
+    pub(crate) validate: Option<Expr>,
+}
+
+impl FieldOptions {
+    pub(crate) fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut options = Self::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("pyo3")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("default") {
+                    if options.default {
+                        return Err(meta.error("`default` may only be specified once"));
+                    }
+                    options.default = true;
+                } else if meta.path.is_ident("rename") {
+                    if options.rename.is_some() {
+                        return Err(meta.error("`rename` may only be specified once"));
+                    }
+                    options.rename = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("alias") {
+                    options.aliases.push(meta.value()?.parse()?);
+                } else if meta.path.is_ident("validate") {
+                    if options.validate.is_some() {
+                        return Err(meta.error("`validate` may only be specified once"));
+                    }
+                    options.validate = Some(meta.value()?.parse()?);
+                } else {
+                    return Err(meta.error(
+                        "expected `default`, `rename = \"...\"`, `alias = \"...\"` or `validate = ...` for `#[derive(FromPyDict/IntoPyDict)]`",
+                    ));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(options)
+    }
+}
+
+/// The named fields of a struct, `derive` is the name of the derive macro for error messages.
+pub(crate) fn struct_fields(data: Data, derive: &str) -> syn::Result<Punctuated<Field, Comma>> {
+    let span = match data {
+        Data::Struct(data) => match data.fields {
+            Fields::Named(fields) => return Ok(fields.named),
+            Fields::Unit => return Ok(Punctuated::new()),
+            Fields::Unnamed(fields) => {
+                return Err(syn::Error::new(
+                    fields.span(),
+                    format!("`#[derive({derive})]` only supports structs with named fields"),
+                ))
+            }
+        },
+        Data::Enum(data) => data.enum_token.span,
+        Data::Union(data) => data.union_token.span,
+    };
+    Err(syn::Error::new(
+        span,
+        format!("`#[derive({derive})]` only supports structs"),
+    ))
+}
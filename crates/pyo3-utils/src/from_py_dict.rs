@@ -4,14 +4,19 @@ use std::borrow::{Cow, ToOwned};
 
 use pyo3::{
     conversion::{FromPyObjectBound, IntoPyObjectExt as _},
-    exceptions::PyTypeError,
+    exceptions::{PyTypeError, PyValueError},
     prelude::*,
     types::{PyDict, PyString},
 };
 
+/// See [FromPyDict](trait@FromPyDict).
+pub use pyo3_utils_macros::FromPyDict;
+/// See [IntoPyDict](trait@IntoPyDict).
+pub use pyo3_utils_macros::IntoPyDict;
+
 /// Inspired by [`typing.NotRequired`](https://docs.python.org/3/library/typing.html#typing.NotRequired)
 ///
-/// See also: [derive_from_py_dict].
+/// See also: [FromPyDict](trait@FromPyDict).
 #[derive(Debug, Clone, Copy)]
 pub struct NotRequired<T>(pub Option<T>);
 
@@ -103,8 +108,145 @@ where
     }
 }
 
+impl<T> NotRequired<T> {
+    /// Sets the item only if the value exists, see [IntoPyDict](trait@IntoPyDict).
+    #[doc(hidden)]
+    pub fn __set_item_to<'a, 'py>(
+        &'a self,
+        dict: &Bound<'py, PyDict>,
+        key: &Bound<'py, PyString>,
+    ) -> PyResult<()>
+    where
+        &'a T: IntoPyObject<'py>,
+    {
+        match &self.0 {
+            Some(value) => dict.set_item(key, value),
+            None => Ok(()),
+        }
+    }
+}
+
 // TODO: once <https://github.com/PyO3/pyo3/issues/5163> is resolved, we can deprecate this trait.
-/// See also: [derive_from_py_dict]
+/// Extracts a struct from a Python `dict`, e.g., the `**kwargs` of a Python function.
+///
+/// > Why we need this trait?
+/// >
+/// > ref: <https://github.com/PyO3/pyo3/issues/5163>
+///
+/// Prefer `#[derive(FromPyDict)]` over [derive_from_py_dict].
+///
+/// # Derive
+///
+/// The derive macro accepts the following `#[pyo3(...)]` options on the fields:
+///
+/// - `default`: use [Default::default] if the key is missing, usually used with [NotRequired].
+/// - `rename = "key"`: use `key` instead of the field name as the dict key.
+/// - `alias = "key"`: also accept `key`, can be specified multiple times.
+///   It's an error if more than one of the keys of a field is present.
+/// - `validate = path::to::fn`: call `fn(&FieldType) -> PyResult<()>` after extracting the field.
+///
+/// The `#[cfg(...)]` attributes on the fields are respected. NOTE: the disabled fields are
+/// removed before the derive macro runs, so their keys are treated as unknown keys.
+/// List them in `ignore_keys(...)` if they should be accepted (e.g., the keys of the other platforms).
+///
+/// And on the struct:
+///
+/// - `ignore_keys("key", ...)`: accept but ignore these keys, can be specified multiple times.
+///   A key which is also the key of an enabled field is extracted as usual.
+/// - `allow_unknown_fields`: ignore all the unknown keys.
+///   By default, an unknown key is rejected with a "did you mean" suggestion.
+/// - `crate = "path::to::pyo3_utils"`: the path to this crate, defaults to `::pyo3_utils`.
+///
+/// The error messages include the field path (e.g., `Foo.bar`),
+/// and the original error is set as the `__cause__`.
+///
+/// NOTE: pyo3's `#[derive(FromPyObject/IntoPyObject)]` also read the `#[pyo3(...)]` options,
+/// so only `default` can be used if they are derived on the same struct.
+///
+/// # Example:
+/**
+```rust
+use pyo3::{
+    exceptions::PyValueError,
+    prelude::*,
+    types::{IntoPyDict as _, PyDict},
+};
+use pyo3_utils::from_py_dict::{FromPyDict, NotRequired};
+
+fn positive(value: &i32) -> PyResult<()> {
+    if *value > 0 {
+        Ok(())
+    } else {
+        Err(PyValueError::new_err("must be positive"))
+    }
+}
+
+#[derive(Debug, FromPyDict)]
+pub struct Foo {
+    #[pyo3(validate = positive)]
+    a: i32,
+    #[pyo3(default, rename = "bee", alias = "b")]
+    b: NotRequired<i32>,
+    #[cfg(all())]
+    #[pyo3(default)]
+    c: NotRequired<Option<i32>>,
+    #[cfg(any())]
+    #[pyo3(default)]
+    d: NotRequired<i32>,
+}
+
+#[derive(Debug, FromPyDict)]
+#[pyo3(ignore_keys("d", "e"))]
+pub struct Bar {
+    a: i32,
+    #[cfg(any())]
+    #[pyo3(default)]
+    d: NotRequired<i32>,
+    #[cfg(all())]
+    #[pyo3(default)]
+    e: NotRequired<i32>,
+}
+
+fn main() -> PyResult<()> {
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let foo = Foo::from_py_dict(&[("a", 1), ("bee", 2)].into_py_dict(py)?)?;
+        assert_eq!((foo.a, foo.b.0, foo.c.0), (1, Some(2), None));
+
+        // alias
+        let foo = Foo::from_py_dict(&[("a", 1), ("b", 2)].into_py_dict(py)?)?;
+        assert_eq!(foo.b.0, Some(2));
+
+        // the key of the `cfg`-disabled field is unknown
+        assert!(Foo::from_py_dict(&[("a", 1), ("d", 2)].into_py_dict(py)?).is_err());
+        // unless it's in `ignore_keys`
+        let bar = Bar::from_py_dict(&[("a", 1), ("d", 2), ("e", 3)].into_py_dict(py)?)?;
+        assert_eq!((bar.a, bar.e.0), (1, Some(3)));
+        // the other unknown keys are still rejected
+        assert!(Bar::from_py_dict(&[("a", 1), ("f", 2)].into_py_dict(py)?).is_err());
+
+        // validator
+        let err = Foo::from_py_dict(&[("a", 0)].into_py_dict(py)?).unwrap_err();
+        assert!(err.is_instance_of::<PyValueError>(py));
+
+        // unknown key
+        let err = Foo::from_py_dict(&[("a", 1), ("bea", 2)].into_py_dict(py)?).unwrap_err();
+        assert_eq!(
+            err.value(py).to_string(),
+            "Foo got an unexpected key 'bea', did you mean 'bee'?"
+        );
+
+        // the field path
+        let dict = PyDict::new(py);
+        dict.set_item("a", "1")?;
+        let err = Foo::from_py_dict(&dict).unwrap_err();
+        assert!(err.value(py).to_string().starts_with("failed to extract field Foo.a"));
+
+        Ok(())
+    })
+}
+```
+*/
 pub trait FromPyDict: Sized {
     fn from_py_dict(dict: &Bound<'_, PyDict>) -> PyResult<Self>;
 }
@@ -133,6 +275,198 @@ where
     Ok(value)
 }
 
+/// The reverse of [FromPyDict](trait@FromPyDict), converts a struct back to a Python `dict`.
+///
+/// NOTE: it's different from [pyo3::types::IntoPyDict], which converts a sequence of key-value pairs.
+///
+/// # Derive
+///
+/// `#[derive(IntoPyDict)]` shares the `#[pyo3(...)]` options with `#[derive(FromPyDict)]`:
+///
+/// - The key is the field name or the `rename = "key"`, the aliases are ignored.
+/// - The [NotRequired] fields are only emitted if they are set, i.e., `NotRequired(Some(..))`.
+///   The other fields are always emitted (e.g., `Option<T>` is emitted as `None`).
+/// - The field type `T` must implement [IntoPyObject] for `&T`.
+///
+/// So `T::from_py_dict(&t.to_py_dict(py)?)` round-trips.
+///
+/// # Example:
+/**
+```rust
+use pyo3::{prelude::*, types::IntoPyDict as _};
+use pyo3_utils::from_py_dict::{FromPyDict, IntoPyDict, NotRequired};
+
+#[derive(FromPyDict, IntoPyDict)]
+pub struct Foo {
+    a: i32,
+    #[pyo3(default, rename = "bee")]
+    b: NotRequired<i32>,
+    #[pyo3(default)]
+    c: NotRequired<Option<i32>>,
+}
+
+fn main() -> PyResult<()> {
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let dict = [("a", 1), ("bee", 2)].into_py_dict(py)?;
+        let foo = Foo::from_py_dict(&dict)?;
+        // `c` is not set, so it's not emitted
+        assert!(foo.to_py_dict(py)?.eq(&dict)?);
+        Ok(())
+    })
+}
+```
+*/
+pub trait IntoPyDict {
+    fn to_py_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>>;
+}
+
+/// Returns the value of the first present key in `keys`,
+/// or an error if more than one of them is present.
+fn get_item_of_keys<'py>(
+    dict: &Bound<'py, PyDict>,
+    keys: &[&Bound<'py, PyString>],
+    struct_name: &'static str,
+    field_name: &'static str,
+) -> PyResult<Option<(Bound<'py, PyAny>, usize)>> {
+    let mut found: Option<(Bound<'py, PyAny>, usize)> = None;
+    for (index, key) in keys.iter().enumerate() {
+        let Some(value) = dict.get_item(key)? else {
+            continue;
+        };
+        if let Some((_, first)) = &found {
+            return Err(PyTypeError::new_err(format!(
+                "{struct_name} got multiple values for field {struct_name}.{field_name}: {} and {}",
+                keys[*first].repr()?,
+                key.repr()?,
+            )));
+        }
+        found = Some((value, index));
+    }
+    Ok(found)
+}
+
+fn extract_value<T>(
+    value: &Bound<'_, PyAny>,
+    key: &Bound<'_, PyString>,
+    struct_name: &'static str,
+    field_name: &'static str,
+) -> PyResult<T>
+where
+    for<'a, 'py> T: FromPyObjectBound<'a, 'py>,
+{
+    value.extract::<T>().map_err(|err| {
+        let py = value.py();
+        let new_err = PyTypeError::new_err(format!(
+            "failed to extract field {struct_name}.{field_name} (key {}): {}",
+            key.repr()
+                .map_or_else(|_| "?".into(), |repr| repr.to_string()),
+            err.value(py),
+        ));
+        new_err.set_cause(py, Some(err));
+        new_err
+    })
+}
+
+#[doc(hidden)]
+pub fn __extract_field<'py, T>(
+    dict: &Bound<'py, PyDict>,
+    keys: &[&Bound<'py, PyString>],
+    struct_name: &'static str,
+    field_name: &'static str,
+) -> PyResult<T>
+where
+    for<'a, 'py_a> T: FromPyObjectBound<'a, 'py_a>,
+{
+    match get_item_of_keys(dict, keys, struct_name, field_name)? {
+        Some((value, index)) => extract_value(&value, keys[index], struct_name, field_name),
+        None => Err(PyTypeError::new_err(format!(
+            "{struct_name} missing required key {} for field {struct_name}.{field_name}",
+            keys[0].repr()?,
+        ))),
+    }
+}
+
+#[doc(hidden)]
+pub fn __extract_field_with_default<'py, T>(
+    dict: &Bound<'py, PyDict>,
+    keys: &[&Bound<'py, PyString>],
+    struct_name: &'static str,
+    field_name: &'static str,
+) -> PyResult<T>
+where
+    for<'a, 'py_a> T: FromPyObjectBound<'a, 'py_a> + Default,
+{
+    match get_item_of_keys(dict, keys, struct_name, field_name)? {
+        Some((value, index)) => extract_value(&value, keys[index], struct_name, field_name),
+        None => Ok(Default::default()),
+    }
+}
+
+#[doc(hidden)]
+pub fn __validate_field(
+    py: Python<'_>,
+    result: PyResult<()>,
+    struct_name: &'static str,
+    field_name: &'static str,
+) -> PyResult<()> {
+    result.map_err(|err| {
+        let new_err = PyValueError::new_err(format!(
+            "invalid field {struct_name}.{field_name}: {}",
+            err.value(py)
+        ));
+        new_err.set_cause(py, Some(err));
+        new_err
+    })
+}
+
+/// The Levenshtein distance, it's only used for the "did you mean" suggestion.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut prev = (0..=b.len()).collect::<Vec<_>>();
+    let mut curr = vec![0; b.len() + 1];
+    for (i, a_char) in a.chars().enumerate() {
+        curr[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let cost = usize::from(a_char != *b_char);
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev[b.len()]
+}
+
+#[doc(hidden)]
+pub fn __check_unknown_keys(
+    dict: &Bound<'_, PyDict>,
+    known_keys: &[&'static str],
+    struct_name: &'static str,
+) -> PyResult<()> {
+    for key in dict.keys() {
+        let key = key
+            .downcast_into::<PyString>()
+            .map_err(|_| PyTypeError::new_err(format!("{struct_name} keys must be strings")))?;
+        let key = key.to_cow()?;
+        if known_keys.contains(&key.as_ref()) {
+            continue;
+        }
+
+        let suggestion = known_keys
+            .iter()
+            .map(|known| (edit_distance(&key, known), known))
+            .filter(|(distance, known)| *distance <= known.len().max(3) / 2)
+            .min_by_key(|(distance, _)| *distance);
+        let msg = match suggestion {
+            Some((_, known)) => {
+                format!("{struct_name} got an unexpected key '{key}', did you mean '{known}'?")
+            }
+            None => format!("{struct_name} got an unexpected key '{key}'"),
+        };
+        return Err(PyTypeError::new_err(msg));
+    }
+    Ok(())
+}
+
 // ref: <https://github.com/PyO3/pyo3/blob/3914daff760fc23aae4602378b4c010332baa920/src/impl_/frompyobject.rs#L82-L93>
 #[doc(hidden)]
 pub fn __failed_to_extract_struct_field<T>(
@@ -152,9 +486,8 @@ pub fn __failed_to_extract_struct_field<T>(
 
 /// Derives the [FromPyDict] trait for a struct.
 ///
-/// > Why we need this trait?
-/// >
-/// > ref: <https://github.com/PyO3/pyo3/issues/5163>
+/// Prefer `#[derive(FromPyDict)]`, which doesn't require listing the fields again
+/// and supports more options, see [FromPyDict](trait@FromPyDict).
 ///
 /// # Example:
 /**
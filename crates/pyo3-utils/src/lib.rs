@@ -7,6 +7,8 @@
 
 #[cfg(feature = "unstable-from-py-dict")]
 pub mod from_py_dict;
+#[cfg(feature = "unstable-log")]
+pub mod log;
 pub mod py_match;
 pub mod py_wrapper;
 #[cfg(feature = "unstable-serde")]
@@ -0,0 +1,405 @@
+//! Bridges the Rust [log] records and the Python [`logging`](https://docs.python.org/3/library/logging.html) module.
+//!
+//! - [PyLogger] forwards the Rust records to Python `logging`,
+//!   the logger name is the record target with `::` replaced by `.` (e.g., `pyfuture.future.rust`).
+//! - [log_py_record] forwards a Python `logging.LogRecord` to the Rust [log::logger],
+//!   the target is the Python logger name with `.` replaced by `::`.
+//!
+//! Both can be enabled at the same time, the records forwarded from one side
+//! will not be forwarded back.
+//!
+//! If you are using `tracing`, enable its `log` feature (or use `tracing-log`)
+//! to emit the events as [log] records.
+
+use std::{borrow::Cow, cell::Cell, thread::LocalKey};
+
+use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use pyo3::{intern, prelude::*, sync::GILOnceCell, types::PyTuple};
+
+thread_local! {
+    /// Whether the current thread is forwarding a Python record to Rust, see [log_py_record].
+    static FROM_PY: Cell<bool> = const { Cell::new(false) };
+    /// Whether the current thread is forwarding a Rust record to Python, see [PyLogger::log].
+    static TO_PY: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Sets [FROM_PY] or [TO_PY] while forwarding, and resets it even if the logger panics.
+struct ForwardingGuard(&'static LocalKey<Cell<bool>>);
+
+impl ForwardingGuard {
+    fn new(forwarding: &'static LocalKey<Cell<bool>>) -> Self {
+        forwarding.set(true);
+        Self(forwarding)
+    }
+}
+
+impl Drop for ForwardingGuard {
+    fn drop(&mut self) {
+        self.0.set(false);
+    }
+}
+
+/// The Python `logging.TRACE` doesn't exist, so we use `5` which is commonly used by Python libraries.
+const PY_TRACE: i32 = 5;
+
+fn py_level(level: Level) -> i32 {
+    match level {
+        Level::Error => 40,
+        Level::Warn => 30,
+        Level::Info => 20,
+        Level::Debug => 10,
+        Level::Trace => PY_TRACE,
+    }
+}
+
+fn rust_level(levelno: i32) -> Level {
+    match levelno {
+        40.. => Level::Error,
+        30..=39 => Level::Warn,
+        20..=29 => Level::Info,
+        10..=19 => Level::Debug,
+        _ => Level::Trace,
+    }
+}
+
+/// A [Log] implementation that forwards the records to Python `logging`.
+///
+/// # NOTE
+///
+/// [PyLogger::log] acquires the GIL, so DO NOT log while holding any lock
+/// that the Python side may wait for, or it may cause deadlock.
+///
+/// # Example
+///
+/// ```ignore
+/// use log::LevelFilter;
+/// use pyo3_utils::log::PyLogger;
+///
+/// PyLogger::init(LevelFilter::Info).unwrap();
+/// // the levels are also filtered by the Python loggers
+/// log::warn!("Hello from Rust");
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+#[non_exhaustive]
+pub struct PyLogger;
+
+static PY_LOGGER: PyLogger = PyLogger;
+
+impl PyLogger {
+    pub const fn new() -> Self {
+        Self
+    }
+
+    /// Sets [PyLogger] as the global [log::logger] with the `max_level`.
+    pub fn init(max_level: LevelFilter) -> Result<(), SetLoggerError> {
+        log::set_logger(&PY_LOGGER)?;
+        log::set_max_level(max_level);
+        Ok(())
+    }
+
+    fn log_with_gil(py: Python<'_>, record: &Record<'_>) -> PyResult<()> {
+        static GET_LOGGER: GILOnceCell<PyObject> = GILOnceCell::new();
+
+        let get_logger = GET_LOGGER.import(py, "logging", "getLogger")?;
+        let name = record.target().replace("::", ".");
+        let logger = get_logger.call1((&name,))?;
+
+        let level = py_level(record.level());
+        if !logger
+            .call_method1(intern!(py, "isEnabledFor"), (level,))?
+            .is_truthy()?
+        {
+            return Ok(());
+        }
+
+        let message = match record.args().as_str() {
+            Some(message) => Cow::Borrowed(message),
+            None => Cow::Owned(record.args().to_string()),
+        };
+        // See: <https://docs.python.org/3/library/logging.html#logging.Logger.makeRecord>
+        let py_record = logger.call_method1(
+            intern!(py, "makeRecord"),
+            (
+                name,
+                level,
+                record.file().unwrap_or("(unknown file)"),
+                record.line().unwrap_or(0),
+                message,
+                PyTuple::empty(py),
+                py.None(),
+            ),
+        )?;
+        logger.call_method1(intern!(py, "handle"), (py_record,))?;
+        Ok(())
+    }
+}
+
+impl Log for PyLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        // don't forward the records that come from Python back
+        if !self.enabled(record.metadata()) || FROM_PY.get() {
+            return;
+        }
+        Python::with_gil(|py| {
+            let _guard = ForwardingGuard::new(&TO_PY);
+            if let Err(e) = Self::log_with_gil(py, record) {
+                e.write_unraisable(py, None);
+            }
+        });
+    }
+
+    fn flush(&self) {}
+}
+
+/// Forwards a Python `logging.LogRecord` to the Rust [log::logger].
+///
+/// It's usually called by a Python `logging.Handler`. The GIL is released while
+/// the Rust logger is handling the record.
+pub fn log_py_record(record: &Bound<'_, PyAny>) -> PyResult<()> {
+    // don't forward the records that come from Rust back
+    if TO_PY.get() {
+        return Ok(());
+    }
+    let py = record.py();
+
+    let level = rust_level(record.getattr(intern!(py, "levelno"))?.extract()?);
+    if level > log::max_level() {
+        return Ok(());
+    }
+    let target = record
+        .getattr(intern!(py, "name"))?
+        .extract::<String>()?
+        .replace('.', "::");
+    let logger = log::logger();
+    if !logger.enabled(&Metadata::builder().level(level).target(&target).build()) {
+        return Ok(());
+    }
+
+    let message: String = record.call_method0(intern!(py, "getMessage"))?.extract()?;
+    let file: Option<String> = record.getattr(intern!(py, "pathname"))?.extract()?;
+    let line: Option<u32> = record.getattr(intern!(py, "lineno"))?.extract()?;
+
+    py.allow_threads(|| {
+        let _guard = ForwardingGuard::new(&FROM_PY);
+        logger.log(
+            &Record::builder()
+                .args(format_args!("{message}"))
+                .level(level)
+                .target(&target)
+                .file(file.as_deref())
+                .line(line)
+                .build(),
+        );
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Mutex, Once, PoisonError};
+
+    use pyo3::types::PyList;
+
+    use super::*;
+
+    /// A Rust logger that records the records, then forwards them to Python by [PyLogger].
+    struct RecordingLogger(Mutex<Vec<(String, Level, String)>>);
+
+    static RECORDING_LOGGER: RecordingLogger = RecordingLogger(Mutex::new(Vec::new()));
+
+    impl Log for RecordingLogger {
+        fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+            PY_LOGGER.enabled(metadata)
+        }
+
+        fn log(&self, record: &Record<'_>) {
+            self.0.lock().unwrap_or_else(PoisonError::into_inner).push((
+                record.target().to_owned(),
+                record.level(),
+                record.args().to_string(),
+            ));
+            PY_LOGGER.log(record);
+        }
+
+        fn flush(&self) {}
+    }
+
+    /// Returns the records of `target` received by [RECORDING_LOGGER].
+    fn rust_records(target: &str) -> Vec<(Level, String)> {
+        RECORDING_LOGGER
+            .0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .filter(|(record_target, ..)| record_target == target)
+            .map(|(_, level, message)| (*level, message.clone()))
+            .collect()
+    }
+
+    #[pyfunction]
+    fn log_record(record: &Bound<'_, PyAny>) -> PyResult<()> {
+        log_py_record(record)
+    }
+
+    const HELPERS: &std::ffi::CStr = cr#"
+import logging
+
+
+class ListHandler(logging.Handler):
+    def __init__(self, records):
+        super().__init__()
+        self.records = records
+
+    def emit(self, record):
+        self.records.append((record.levelno, record.getMessage()))
+
+
+class RustHandler(logging.Handler):
+    """The same as `pytauri.log.RustHandler`."""
+
+    def __init__(self, log_record):
+        super().__init__()
+        self.log_record = log_record
+
+    def emit(self, record):
+        self.log_record(record)
+
+
+def logger(name, records, log_record=None):
+    logger = logging.getLogger(name)
+    logger.setLevel(1)
+    logger.propagate = False
+    logger.addHandler(ListHandler(records))
+    if log_record is not None:
+        logger.addHandler(RustHandler(log_record))
+    return logger
+"#;
+
+    /// Sets [RECORDING_LOGGER] as the global logger, and returns the Python logger `name`
+    /// which appends `(levelno, message)` to `records`.
+    ///
+    /// If `to_rust`, the Python logger also forwards the records to Rust by [log_py_record].
+    fn setup<'py>(
+        py: Python<'py>,
+        name: &str,
+        records: &Bound<'py, PyList>,
+        to_rust: bool,
+    ) -> Bound<'py, PyAny> {
+        static INIT: Once = Once::new();
+        INIT.call_once(|| {
+            log::set_logger(&RECORDING_LOGGER).unwrap();
+            log::set_max_level(LevelFilter::Trace);
+        });
+
+        let helpers = PyModule::from_code(py, HELPERS, c"log_test.py", c"log_test").unwrap();
+        let log_record = if to_rust {
+            Some(wrap_pyfunction!(log_record, &helpers).unwrap())
+        } else {
+            None
+        };
+        helpers
+            .call_method1("logger", (name, records, log_record))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_rust_to_python() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let records = PyList::empty(py);
+            let logger = setup(py, "pyo3_utils_test.rust_to_py", &records, false);
+
+            py.allow_threads(|| {
+                for level in [
+                    Level::Error,
+                    Level::Warn,
+                    Level::Info,
+                    Level::Debug,
+                    Level::Trace,
+                ] {
+                    log::log!(target: "pyo3_utils_test::rust_to_py", level, "{level}");
+                }
+            });
+            let records: Vec<(i32, String)> = records.extract().unwrap();
+            assert_eq!(
+                records,
+                [
+                    (40, "ERROR".to_owned()),
+                    (30, "WARN".to_owned()),
+                    (20, "INFO".to_owned()),
+                    (10, "DEBUG".to_owned()),
+                    (PY_TRACE, "TRACE".to_owned()),
+                ]
+            );
+
+            // the levels are also filtered by the Python loggers
+            logger.call_method1("setLevel", (30,)).unwrap();
+            py.allow_threads(|| {
+                log::info!(target: "pyo3_utils_test::rust_to_py", "filtered");
+            });
+            assert_eq!(records.len(), 5);
+        });
+    }
+
+    #[test]
+    fn test_python_to_rust() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let records = PyList::empty(py);
+            let logger = setup(py, "pyo3_utils_test.py_to_rust", &records, true);
+
+            logger
+                .call_method1("warning", ("hello %s", "rust"))
+                .unwrap();
+            for levelno in [25, PY_TRACE, 1] {
+                logger
+                    .call_method1("log", (levelno, "custom level"))
+                    .unwrap();
+            }
+
+            assert_eq!(
+                rust_records("pyo3_utils_test::py_to_rust"),
+                [
+                    (Level::Warn, "hello rust".to_owned()),
+                    (Level::Info, "custom level".to_owned()),
+                    (Level::Trace, "custom level".to_owned()),
+                    (Level::Trace, "custom level".to_owned()),
+                ]
+            );
+        });
+    }
+
+    /// Both [PyLogger] and the Python handler forwarding to Rust are installed.
+    #[test]
+    fn test_no_recursion() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let records = PyList::empty(py);
+            let logger = setup(py, "pyo3_utils_test.both", &records, true);
+
+            // Python -> Rust, not forwarded back to Python
+            logger.call_method1("warning", ("from python",)).unwrap();
+            // Rust -> Python, not forwarded back to Rust
+            py.allow_threads(|| {
+                log::warn!(target: "pyo3_utils_test::both", "from rust");
+            });
+
+            let records: Vec<(i32, String)> = records.extract().unwrap();
+            assert_eq!(
+                records,
+                [(30, "from python".to_owned()), (30, "from rust".to_owned())]
+            );
+            assert_eq!(
+                rust_records("pyo3_utils_test::both"),
+                [
+                    (Level::Warn, "from python".to_owned()),
+                    (Level::Warn, "from rust".to_owned()),
+                ]
+            );
+        });
+    }
+}
@@ -1,3 +1,46 @@
+//! Converts between Rust [serde] types and Python objects with [PySerde].
+//!
+//! # Borrowed deserialization
+//!
+//! The `PySerde::from_*` and [PySerde::extract_with] methods borrow `ob` for `'de`,
+//! so `T` can contain `&'de str` or `&'de [u8]` (see [`#[serde(borrow)]`](https://serde.rs/lifetimes.html))
+//! that point into the backing memory of the Python `bytes` or `str` without copying,
+//! as long as the `&Bound` (and so the GIL) lives.
+//!
+//! NOTE:
+//!
+//! - [PySerde::from_object] (i.e., [pythonize]) can't borrow the data, neither can the JSON strings
+//!   that contain escapes, use `#[serde(borrow)] Cow<'de, str>` if you need to accept them.
+//! - [FromPyObject] requires [DeserializeOwned], see [PySerde::extract_bound].
+//!
+//! # Example
+//!
+/*!
+```rust
+use pyo3::{prelude::*, types::PyBytes};
+use pyo3_utils::serde::{serde::Deserialize, Format, PySerde};
+
+#[derive(Deserialize)]
+#[serde(crate = "pyo3_utils::serde::serde")]
+struct Body<'a> {
+    name: &'a str,
+}
+
+fn main() -> PyResult<()> {
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let ob = PyBytes::new(py, br#"{"name": "pytauri"}"#);
+        let body = PySerde::<Body<'_>>::from_bytes(&ob, Format::Json)?.into_inner();
+        assert_eq!(body.name, "pytauri");
+        // zero-copy: `name` points into the `bytes` object
+        assert!(ob.as_bytes().as_ptr_range().contains(&body.name.as_ptr()));
+        Ok(())
+    })
+}
+```
+*/
+use std::fmt::Display;
+
 use pyo3::{
     exceptions::PyValueError,
     prelude::*,
@@ -5,22 +48,35 @@ use pyo3::{
 };
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
+#[cfg(feature = "unstable-serde-cbor")]
+pub use cbor4ii;
 pub use pythonize;
+#[cfg(feature = "unstable-serde-msgpack")]
+pub use rmp_serde;
 pub use serde;
 pub use serde_json;
 
-struct SerdeJsonError(serde_json::Error);
+/// Converts the errors of the serde formats to `ValueError`.
+struct SerdeError<E>(E);
 
-impl From<serde_json::Error> for SerdeJsonError {
-    fn from(e: serde_json::Error) -> Self {
-        Self(e)
+impl<E: Display> From<SerdeError<E>> for PyErr {
+    fn from(e: SerdeError<E>) -> Self {
+        PyValueError::new_err(e.0.to_string())
     }
 }
 
-impl From<SerdeJsonError> for PyErr {
-    fn from(e: SerdeJsonError) -> Self {
-        PyValueError::new_err(e.0.to_string())
-    }
+/// The format of the Python `bytes`, see [PySerde::from_bytes] and [PySerde::to_bytes].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum Format {
+    #[default]
+    Json,
+    /// The maps are serialized with the field names (i.e., [rmp_serde::to_vec_named]),
+    /// so they can be unpacked as `dict` by Python `msgpack`.
+    #[cfg(feature = "unstable-serde-msgpack")]
+    MessagePack,
+    #[cfg(feature = "unstable-serde-cbor")]
+    Cbor,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
@@ -50,12 +106,23 @@ where
     T: Deserialize<'de>,
 {
     pub fn from_json_str<'py>(ob: &'de Bound<'py, PyString>) -> PyResult<Self> {
-        let de = serde_json::from_str(ob.to_str()?).map_err(SerdeJsonError::from)?;
+        let de = serde_json::from_str(ob.to_str()?).map_err(SerdeError)?;
         Ok(Self(de))
     }
 
     pub fn from_json_bytes<'py>(ob: &'de Bound<'py, PyBytes>) -> PyResult<Self> {
-        let de = serde_json::from_slice(ob.as_bytes()).map_err(SerdeJsonError::from)?;
+        Self::from_bytes(ob, Format::Json)
+    }
+
+    pub fn from_bytes<'py>(ob: &'de Bound<'py, PyBytes>, format: Format) -> PyResult<Self> {
+        let bytes = ob.as_bytes();
+        let de = match format {
+            Format::Json => serde_json::from_slice(bytes).map_err(SerdeError)?,
+            #[cfg(feature = "unstable-serde-msgpack")]
+            Format::MessagePack => rmp_serde::from_slice(bytes).map_err(SerdeError)?,
+            #[cfg(feature = "unstable-serde-cbor")]
+            Format::Cbor => cbor4ii::serde::from_slice(bytes).map_err(SerdeError)?,
+        };
         Ok(Self(de))
     }
 
@@ -64,9 +131,17 @@ where
         Ok(Self(de))
     }
 
+    /// Same as [PySerde::extract_with] with [Format::Json].
     pub fn extract<'py>(ob: &'de Bound<'py, PyAny>) -> PyResult<Self> {
+        Self::extract_with(ob, Format::Json)
+    }
+
+    /// - `bytes`: deserialized with `format`
+    /// - `str`: always deserialized as JSON
+    /// - otherwise: depythonized, see [PySerde::from_object]
+    pub fn extract_with<'py>(ob: &'de Bound<'py, PyAny>, format: Format) -> PyResult<Self> {
         if let Ok(v) = ob.downcast::<PyBytes>() {
-            Self::from_json_bytes(v)
+            Self::from_bytes(v, format)
         } else if let Ok(v) = ob.downcast::<PyString>() {
             Self::from_json_str(v)
         } else {
@@ -83,7 +158,8 @@ where
     /// We need to wait for [pyo3::conversion::FromPyObjectBound].
     /// See: <https://github.com/PyO3/pyo3/pull/4390>.
     ///
-    /// Use [PySerde::extract] as a workaround for now.
+    /// Use [PySerde::extract] (or [PySerde::extract_with]) as a workaround for now,
+    /// see the [module-level documentation](self) for the borrowed deserialization.
     #[inline]
     fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
         Self::extract(ob)
@@ -99,12 +175,22 @@ where
     T: Serialize,
 {
     pub fn to_json_str<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyString>> {
-        let val = serde_json::to_string(&self.0).map_err(SerdeJsonError::from)?;
+        let val = serde_json::to_string(&self.0).map_err(SerdeError)?;
         Ok(PyString::new(py, &val))
     }
 
     pub fn to_json_bytes<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
-        let val = serde_json::to_vec(&self.0).map_err(SerdeJsonError::from)?;
+        self.to_bytes(py, Format::Json)
+    }
+
+    pub fn to_bytes<'py>(&self, py: Python<'py>, format: Format) -> PyResult<Bound<'py, PyBytes>> {
+        let val = match format {
+            Format::Json => serde_json::to_vec(&self.0).map_err(SerdeError)?,
+            #[cfg(feature = "unstable-serde-msgpack")]
+            Format::MessagePack => rmp_serde::to_vec_named(&self.0).map_err(SerdeError)?,
+            #[cfg(feature = "unstable-serde-cbor")]
+            Format::Cbor => cbor4ii::serde::to_vec(Vec::new(), &self.0).map_err(SerdeError)?,
+        };
         Ok(PyBytes::new(py, &val))
     }
 
@@ -0,0 +1,5 @@
+fn main() {
+    // for `#[cfg(any(not(Py_LIMITED_API), Py_3_11))]`,
+    // see <https://pyo3.rs/v0.23.2/building-and-distribution/multiple-python-versions.html#using-pyo3-build-config>
+    pyo3_build_config::use_pyo3_cfgs();
+}
@@ -1,19 +1,37 @@
-use std::{borrow::Cow, str::FromStr as _};
+use std::{
+    num::NonZeroUsize,
+    str::FromStr as _,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex, PoisonError, Weak,
+    },
+};
 
+#[cfg(any(not(Py_LIMITED_API), Py_3_11))]
+use pyo3::buffer::PyBuffer;
 use pyo3::{
-    exceptions::PyValueError,
+    exceptions::{PyBaseException, PyRuntimeError, PyTypeError, PyValueError},
     intern,
     prelude::*,
     pybacked::{PyBackedBytes, PyBackedStr},
-    types::{PyBytes, PyDict, PyList, PyString, PyType},
+    types::{PyBytes, PyDict, PyList, PyMemoryView, PyString, PyTuple, PyType},
+};
+use pyo3_utils::{
+    py_wrapper::{ConsumedError, PyWrapper, PyWrapperT0, PyWrapperT2},
+    serde::{serde::Serialize, serde_json, PySerde},
 };
-use pyo3_utils::py_wrapper::{PyWrapper, PyWrapperT0, PyWrapperT2};
-use tauri::ipc::{self, CommandArg as _, CommandItem, InvokeBody, InvokeMessage};
+use tauri::http::{header::GetAll, HeaderMap, HeaderName, HeaderValue};
+use tauri::ipc::{
+    self, CommandArg as _, CommandItem, CommandScope, GlobalScope, InvokeBody, InvokeMessage,
+};
+use tauri::Manager;
+use tokio::sync::mpsc::{self as tokio_mpsc, error::TrySendError};
 
 use crate::{
     ext_mod::{
         webview::{Webview, WebviewWindow},
-        PyAppHandleExt as _, StateManager,
+        window::Window,
+        PyAppHandleExt as _, StateManager, Url, IS_DEV,
     },
     tauri_runtime::Runtime,
     utils::TauriError,
@@ -22,25 +40,349 @@ use crate::{
 type IpcInvoke = tauri::ipc::Invoke<Runtime>;
 type IpcInvokeResolver = tauri::ipc::InvokeResolver<Runtime>;
 type TauriWebviewWindow = tauri::webview::WebviewWindow<Runtime>;
+type TauriWebview = tauri::webview::Webview<Runtime>;
 type TauriInvokeResponseBody = tauri::ipc::InvokeResponseBody;
+type TauriUrl = tauri::Url;
+type IpcAcl = Option<Vec<tauri::utils::acl::resolved::ResolvedCommand>>;
 
-// PERF, TODO: maybe we should use `downcast` to manually implement `FromPyObject`,
-// because `derive(FromPyObject)` will be based on `extract`,
-// which has higher overhead on errors
-#[derive(FromPyObject)]
+/// The body of an IPC response, see Python `_InvokeResponseBody`.
 enum InvokeResponseBody {
     // NOTE: Json appears more frequently, so we put it first.
     Json(PyBackedStr),
     // NOTE: use `Cow<[u8]>` instead of `Vec<u8>`,
     // see: <https://github.com/PyO3/pyo3/issues/2888>
     Raw(PyBackedBytes),
+    /// Any C-contiguous object supporting the buffer protocol, e.g., `bytearray`, `memoryview`, `numpy.ndarray`.
+    ///
+    /// It's viewed as bytes (`memoryview.cast("B")`), so we can copy it without the GIL.
+    #[cfg(any(not(Py_LIMITED_API), Py_3_11))]
+    Buffer(PyBuffer<u8>),
 }
 
-impl From<InvokeResponseBody> for TauriInvokeResponseBody {
-    fn from(value: InvokeResponseBody) -> Self {
-        match value {
-            InvokeResponseBody::Json(json) => TauriInvokeResponseBody::Json(json.to_owned()),
-            InvokeResponseBody::Raw(raw) => TauriInvokeResponseBody::Raw(raw.to_owned()),
+impl<'py> FromPyObject<'py> for InvokeResponseBody {
+    // NOTE: we manually implement `FromPyObject` with `downcast`,
+    // because `derive(FromPyObject)` is based on `extract`, which has higher overhead on errors.
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        if let Ok(json) = ob.downcast::<PyString>() {
+            return Ok(Self::Json(json.clone().try_into()?));
+        }
+        if let Ok(raw) = ob.downcast::<PyBytes>() {
+            return Ok(Self::Raw(raw.clone().into()));
+        }
+
+        let py = ob.py();
+        let view = PyMemoryView::from(ob).map_err(|_| {
+            PyTypeError::new_err(format!(
+                "expected `str`, `bytes` or an object supporting the buffer protocol, got `{}`",
+                ob.get_type()
+            ))
+        })?;
+
+        #[cfg(any(not(Py_LIMITED_API), Py_3_11))]
+        {
+            // `cast` fails if the buffer is not C-contiguous,
+            // in this case, we fallback to `tobytes` (which makes a contiguous copy).
+            if let Ok(bytes_view) = view.call_method1(intern!(py, "cast"), (intern!(py, "B"),)) {
+                return Ok(Self::Buffer(PyBuffer::get(&bytes_view)?));
+            }
+        }
+
+        // PERF: this copies the buffer twice (`tobytes` and `to_owned`),
+        // but it only happens for non-contiguous buffers
+        // (or on the limited API before Python 3.11, which has no buffer API).
+        let raw = view
+            .call_method0(intern!(py, "tobytes"))?
+            .downcast_into::<PyBytes>()?;
+        Ok(Self::Raw(raw.into()))
+    }
+}
+
+impl InvokeResponseBody {
+    /// Copies the body into [TauriInvokeResponseBody], it doesn't require the GIL.
+    ///
+    /// NOTE: don't drop `self` without the GIL, because dropping [PyBuffer] will acquire the GIL.
+    fn to_tauri(&self) -> TauriInvokeResponseBody {
+        match self {
+            InvokeResponseBody::Json(json) => TauriInvokeResponseBody::Json(json.to_string()),
+            InvokeResponseBody::Raw(raw) => TauriInvokeResponseBody::Raw(raw.to_vec()),
+            #[cfg(any(not(Py_LIMITED_API), Py_3_11))]
+            InvokeResponseBody::Buffer(buffer) => {
+                debug_assert!(buffer.is_c_contiguous());
+                // SAFETY: the buffer is a C-contiguous byte buffer (see [FromPyObject] impl),
+                // and it's kept alive (and can't be resized) by [PyBuffer].
+                //
+                // NOTE: it's still possible that the other threads are writing the buffer,
+                // just like the other Python C extensions which release the GIL,
+                // it's the user's responsibility to avoid this.
+                let bytes = unsafe {
+                    std::slice::from_raw_parts(buffer.buf_ptr() as *const u8, buffer.len_bytes())
+                };
+                TauriInvokeResponseBody::Raw(bytes.to_vec())
+            }
+        }
+    }
+}
+
+/// The value of `reject`, please refer to the Python-side documentation.
+enum InvokeRejection {
+    /// `str` is rejected as is, unless `json=True`
+    Message(PyBackedStr),
+    Json(serde_json::Value),
+}
+
+impl InvokeRejection {
+    fn extract(value: &Bound<'_, PyAny>, json: bool) -> PyResult<Self> {
+        if let Ok(value) = value.downcast::<PyString>() {
+            if json {
+                let value = PySerde::<serde_json::Value>::from_json_str(value)?;
+                Ok(Self::Json(value.into_inner()))
+            } else {
+                Ok(Self::Message(value.extract()?))
+            }
+        } else {
+            // - `bytes`: JSON bytes
+            // - otherwise: any object that can be depythonized
+            let value = PySerde::<serde_json::Value>::extract(value)?;
+            Ok(Self::Json(value.into_inner()))
+        }
+    }
+
+    fn reject(self, resolver: InvokeResponder) {
+        match self {
+            Self::Message(message) => resolver.reject(&*message),
+            Self::Json(value) => resolver.reject(value),
+        }
+    }
+}
+
+/// Serializes a Python exception as a JSON object:
+///
+/// ```json
+/// {
+///     "type": "<qualname of the exception type>",
+///     "message": "<str(exception)>",
+///     "args": ["<exception.args, or their repr if they are not serializable>"],
+///     "traceback": "<only if `traceback` is true>"
+/// }
+/// ```
+///
+/// This function never fails, if some information can not be obtained,
+/// the corresponding field will be a placeholder.
+pub fn exception_to_json(
+    exception: &Bound<'_, PyBaseException>,
+    traceback: bool,
+) -> serde_json::Value {
+    const UNKNOWN: &str = "<unknown>";
+
+    let py = exception.py();
+
+    let r#type = exception
+        .get_type()
+        .qualname()
+        .map(|name| name.to_string())
+        .unwrap_or_else(|_| UNKNOWN.to_owned());
+    let message = exception
+        .str()
+        .map(|msg| msg.to_string())
+        .unwrap_or_else(|_| UNKNOWN.to_owned());
+    let args = exception
+        .getattr(intern!(py, "args"))
+        .and_then(|args| args.downcast_into::<PyTuple>().map_err(PyErr::from))
+        .map(|args| {
+            args.iter()
+                .map(
+                    |arg| match PySerde::<serde_json::Value>::from_object(&arg) {
+                        Ok(arg) => arg.into_inner(),
+                        Err(_) => arg
+                            .repr()
+                            .map(|repr| repr.to_string())
+                            .unwrap_or_else(|_| UNKNOWN.to_owned())
+                            .into(),
+                    },
+                )
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut json = serde_json::Map::new();
+    json.insert("type".to_owned(), r#type.into());
+    json.insert("message".to_owned(), message.into());
+    json.insert("args".to_owned(), serde_json::Value::Array(args));
+    if traceback {
+        let traceback = format_traceback(exception).unwrap_or_else(|_| UNKNOWN.to_owned());
+        json.insert("traceback".to_owned(), traceback.into());
+    }
+    serde_json::Value::Object(json)
+}
+
+fn format_traceback(exception: &Bound<'_, PyBaseException>) -> PyResult<String> {
+    let py = exception.py();
+    // NOTE: use the three-argument form for py39 compatibility
+    let lines = py.import("traceback")?.call_method1(
+        intern!(py, "format_exception"),
+        (
+            exception.get_type(),
+            exception,
+            exception.getattr(intern!(py, "__traceback__"))?,
+        ),
+    )?;
+    let traceback = PyString::new(py, "").call_method1(intern!(py, "join"), (lines,))?;
+    traceback.extract()
+}
+
+/// The rejection used when the Python side drops the ipc request without responding.
+const DROPPED_REJECTION: &str = "python handler dropped the request without responding";
+
+/// The error raised when responding to a cancelled ipc request, see [PendingResolver::try_take].
+const CANCELLED_ERROR_MSG: &str =
+    "the command has already been cancelled (e.g., its deadline has passed)";
+
+/// The [tauri::ipc::InvokeResolver] shared by [Invoke], [InvokeResolver] and [InvokeCancelHandle].
+///
+/// If it is dropped without being consumed, the ipc will be rejected with [DROPPED_REJECTION],
+/// so the frontend `invoke()` promise always settles.
+struct PendingResolver {
+    resolver: PyWrapper<PyWrapperT2<IpcInvokeResolver>>,
+    cancelled: AtomicBool,
+    on_complete: Mutex<Option<InvokeCompleteFn>>,
+}
+
+/// How an ipc request is responded, see [Invoke::set_on_complete].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InvokeOutcome {
+    Resolved,
+    /// Including the rejections by the deadline or the raised exceptions.
+    Rejected,
+    /// Rejected automatically because the Python side dropped it without responding.
+    Dropped,
+}
+
+type InvokeCompleteFn = Box<dyn FnOnce(InvokeOutcome, &ipc::InvokeResponse) + Send + 'static>;
+
+impl PendingResolver {
+    #[cfg(feature = "__private")]
+    fn new(resolver: IpcInvokeResolver) -> Arc<Self> {
+        Arc::new(Self {
+            resolver: PyWrapper::new2(resolver),
+            cancelled: AtomicBool::new(false),
+            on_complete: Mutex::new(None),
+        })
+    }
+
+    /// Takes the resolver to respond.
+    ///
+    /// If it has been cancelled (see [InvokeCancelHandle::cancel]), raises `RuntimeError`
+    /// and [InvokeResolver::cancelled] is guaranteed to be `True` at that time.
+    fn try_take(&self) -> PyResult<InvokeResponder> {
+        loop {
+            match self.resolver.try_take_inner() {
+                Ok(Ok(resolver)) => return Ok(self.responder(resolver)),
+                Ok(Err(_)) if self.cancelled.load(Ordering::Relaxed) => {
+                    return Err(PyRuntimeError::new_err(CANCELLED_ERROR_MSG))
+                }
+                Ok(Err(e)) => return Err(e.into()),
+                // NOTE: the lock is only held for a moment (e.g., by [InvokeCancelHandle::cancel]),
+                // so we retry to get the definite result instead of a spurious `LockError`.
+                Err(_) => std::thread::yield_now(),
+            }
+        }
+    }
+
+    fn responder(&self, resolver: IpcInvokeResolver) -> InvokeResponder {
+        let on_complete = self
+            .on_complete
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        InvokeResponder {
+            resolver,
+            on_complete,
+        }
+    }
+}
+
+impl Drop for PendingResolver {
+    fn drop(&mut self) {
+        if let Ok(Ok(resolver)) = self.resolver.try_take_inner() {
+            self.responder(resolver)
+                .respond(InvokeOutcome::Dropped, Err(DROPPED_REJECTION.into()));
+        }
+    }
+}
+
+/// The [tauri::ipc::InvokeResolver] taken from a pending [Invoke],
+/// it calls the callback of [Invoke::set_on_complete] when responding.
+pub struct InvokeResponder {
+    resolver: IpcInvokeResolver,
+    on_complete: Option<InvokeCompleteFn>,
+}
+
+impl InvokeResponder {
+    fn respond(
+        self,
+        outcome: InvokeOutcome,
+        response: Result<TauriInvokeResponseBody, ipc::InvokeError>,
+    ) {
+        let response = match response {
+            Ok(body) => ipc::InvokeResponse::Ok(body),
+            Err(error) => ipc::InvokeResponse::Err(error),
+        };
+        if let Some(on_complete) = self.on_complete {
+            on_complete(outcome, &response);
+        }
+        match response {
+            ipc::InvokeResponse::Ok(body) => self.resolver.resolve(body),
+            ipc::InvokeResponse::Err(error) => self.resolver.invoke_error(error),
+        }
+    }
+
+    /// See [tauri::ipc::InvokeResolver::resolve].
+    pub fn resolve(self, body: TauriInvokeResponseBody) {
+        self.respond(InvokeOutcome::Resolved, Ok(body));
+    }
+
+    /// See [tauri::ipc::InvokeResolver::reject].
+    pub fn reject<T: Serialize>(self, value: T) {
+        self.respond(InvokeOutcome::Rejected, Err(value.into()));
+    }
+
+    /// See [tauri::ipc::InvokeResolver::invoke_error].
+    pub fn invoke_error(self, error: ipc::InvokeError) {
+        self.respond(InvokeOutcome::Rejected, Err(error));
+    }
+}
+
+/// A handle to cancel a pending [Invoke], see [Invoke::cancel_handle].
+///
+/// It does not keep the ipc request alive.
+#[cfg(feature = "__private")]
+pub struct InvokeCancelHandle(Weak<PendingResolver>);
+
+#[cfg(feature = "__private")]
+impl InvokeCancelHandle {
+    /// If the ipc has not been responded yet, rejects it with `reason`
+    /// and sets [InvokeResolver::cancelled] to notify the Python side.
+    ///
+    /// Returns `true` if the ipc was pending.
+    pub fn cancel(&self, reason: &str) -> bool {
+        let Some(pending) = self.0.upgrade() else {
+            return false;
+        };
+        // - `LockError`: it is being responded now
+        let Ok(mut guard) = pending.resolver.try_write() else {
+            return false;
+        };
+        match std::mem::replace(&mut *guard, Err(ConsumedError)) {
+            Ok(resolver) => {
+                // NOTE: set it before releasing the lock, so [PendingResolver::try_take]
+                // always sees it once the resolver has been taken by us.
+                pending.cancelled.store(true, Ordering::Relaxed);
+                drop(guard);
+                pending.responder(resolver).reject(reason);
+                true
+            }
+            // it has already been responded
+            Err(_) => false,
         }
     }
 }
@@ -49,16 +391,16 @@ impl From<InvokeResponseBody> for TauriInvokeResponseBody {
 #[pyclass(frozen, generic)]
 #[non_exhaustive]
 pub struct InvokeResolver {
-    inner: PyWrapper<PyWrapperT2<IpcInvokeResolver>>,
+    inner: Arc<PendingResolver>,
     #[pyo3(get)]
     arguments: Py<PyDict>,
 }
 
 impl InvokeResolver {
     #[inline]
-    fn new(resolver: IpcInvokeResolver, arguments: Py<PyDict>) -> Self {
+    fn new(resolver: Arc<PendingResolver>, arguments: Py<PyDict>) -> Self {
         Self {
-            inner: PyWrapper::new2(resolver),
+            inner: resolver,
             arguments,
         }
     }
@@ -67,39 +409,95 @@ impl InvokeResolver {
 #[pymethods]
 // NOTE: These pymethods implementation must not block
 impl InvokeResolver {
+    #[getter]
+    fn cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::Relaxed)
+    }
+
     fn resolve(&self, py: Python<'_>, value: InvokeResponseBody) -> PyResult<()> {
         // NOTE: This function implementation must not block
         py.allow_threads(|| {
-            let resolver = self.inner.try_take_inner()??;
-            resolver.resolve(TauriInvokeResponseBody::from(value));
+            let resolver = self.inner.try_take()?;
+            resolver.resolve(value.to_tauri());
             Ok(())
         })
     }
 
-    // TODO: Support more Python types. Tauri seems to only support `serde` types,
-    // and not support `Raw: Vec<[u8]>`. We should open an issue to ask them about this.
-    //
-    // TODO, PERF: once we drop py39, we can use `value: &str` instead of `Cow<'_, str>`.
-    fn reject(&self, py: Python<'_>, value: Cow<'_, str>) -> PyResult<()> {
+    // NOTE: Tauri only supports `serde` types for rejection, not `Raw: Vec<[u8]>`.
+    #[pyo3(signature = (value, /, *, json = false))]
+    fn reject(&self, value: &Bound<'_, PyAny>, json: bool) -> PyResult<()> {
         // NOTE: This function implementation must not block
-        py.allow_threads(|| {
-            let resolver = self.inner.try_take_inner()??;
+        let rejection = InvokeRejection::extract(value, json)?;
+        value.py().allow_threads(|| {
+            let resolver = self.inner.try_take()?;
+            rejection.reject(resolver);
+            Ok(())
+        })
+    }
+
+    #[pyo3(signature = (exception, /, *, traceback = None))]
+    fn reject_exception(
+        &self,
+        exception: &Bound<'_, PyBaseException>,
+        traceback: Option<bool>,
+    ) -> PyResult<()> {
+        // NOTE: This function implementation must not block
+        let value = exception_to_json(exception, traceback.unwrap_or(IS_DEV));
+        exception.py().allow_threads(|| {
+            let resolver = self.inner.try_take()?;
             resolver.reject(value);
             Ok(())
         })
     }
 }
 
+/// The value of [Invoke::BODY_FORMAT_KEY], decides how the ipc body is passed to Python.
+///
+/// It works for both [InvokeBody::Raw] (`pyInvoke`) and [InvokeBody::Json] (`invoke()`),
+/// so the handler will get the same type regardless of the transport.
+#[derive(Default, Clone, Copy)]
+enum BodyFormat {
+    /// `"bytes"`: the raw body, or the serialized JSON bytes of [InvokeBody::Json]
+    #[default]
+    Bytes,
+    /// `"object"`: the pythonized JSON value, [InvokeBody::Raw] will be deserialized as JSON first
+    Object,
+}
+
+impl BodyFormat {
+    fn extract(value: &Bound<'_, PyAny>) -> PyResult<Self> {
+        let value = value.downcast::<PyString>()?.to_cow()?;
+        match &*value {
+            "bytes" => Ok(Self::Bytes),
+            "object" => Ok(Self::Object),
+            _ => Err(PyValueError::new_err(format!(
+                "Invalid `{}`: `{value}`, expected `bytes` or `object`",
+                Invoke::BODY_FORMAT_KEY
+            ))),
+        }
+    }
+}
+
+/// [tauri::ipc::Invoke] without the resolver, the resolver is [PendingResolver].
+struct InvokeRequest {
+    message: InvokeMessage<Runtime>,
+    acl: IpcAcl,
+    resolver: Arc<PendingResolver>,
+}
+
 /// Please refer to the Python-side documentation
 #[pyclass(frozen)]
 #[non_exhaustive]
 pub struct Invoke {
-    inner: PyWrapper<PyWrapperT2<IpcInvoke>>,
+    inner: PyWrapper<PyWrapperT2<InvokeRequest>>,
     #[pyo3(get)]
     command: Py<PyString>,
 }
 
 impl Invoke {
+    /// Uses the `pyfunc` header as the command name,
+    /// i.e., the frontend calls `invoke("plugin:pytauri|pyfunc", ..., { headers: { pyfunc: "<command>" } })`.
+    ///
     /// If the frontend makes an illegal IPC call, it will automatically reject and return [None]
     #[cfg(feature = "__private")]
     pub fn new(py: Python<'_>, invoke: IpcInvoke) -> Option<Self> {
@@ -116,15 +514,96 @@ impl Invoke {
         //     > TODO, XXX: 👆 is this right?
         let command = PyString::new(py, func_name).unbind();
 
-        let slf = Self {
-            inner: PyWrapper::new2(invoke),
-            command,
+        Some(Self::from_parts(invoke, command))
+    }
+
+    /// Uses [InvokeMessage::command] as the command name instead of the `pyfunc` header,
+    /// i.e., the frontend calls `invoke("plugin:pytauri|<command>")` directly.
+    #[cfg(feature = "__private")]
+    pub fn from_command(py: Python<'_>, invoke: IpcInvoke) -> Self {
+        // TODO, PERF: may be we should use [PyString::intern] ? See [Invoke::new].
+        let command = PyString::new(py, invoke.message.command()).unbind();
+        Self::from_parts(invoke, command)
+    }
+
+    #[cfg(feature = "__private")]
+    #[inline]
+    fn from_parts(invoke: IpcInvoke, command: Py<PyString>) -> Self {
+        let IpcInvoke {
+            message,
+            resolver,
+            acl,
+        } = invoke;
+        let request = InvokeRequest {
+            message,
+            acl,
+            resolver: PendingResolver::new(resolver),
         };
-        Some(slf)
+
+        Self {
+            inner: PyWrapper::new2(request),
+            command,
+        }
+    }
+
+    /// The name of the current command.
+    #[cfg(feature = "__private")]
+    pub fn command<'a, 'py>(&'a self, py: Python<'py>) -> &'a Bound<'py, PyString> {
+        self.command.bind(py)
+    }
+
+    /// Gets a handle to cancel this ipc request (e.g., when a deadline passes).
+    ///
+    /// The handle also works after this [Invoke] has been bound to an [InvokeResolver].
+    /// Returns [None] if this [Invoke] has already been consumed.
+    #[cfg(feature = "__private")]
+    pub fn cancel_handle(&self) -> Option<InvokeCancelHandle> {
+        let request = self.inner.try_lock_inner_ref().ok()?.ok()?;
+        Some(InvokeCancelHandle(Arc::downgrade(&request.resolver)))
+    }
+
+    /// Sets the callback that will be called once when the ipc is responded (or dropped),
+    /// it works even after this [Invoke] has been bound to an [InvokeResolver].
+    ///
+    /// `on_complete` is called with the response before it is sent to the frontend,
+    /// on the responding thread, so it must be cheap and must not block.
+    /// Does nothing if this [Invoke] has already been consumed.
+    #[cfg(feature = "__private")]
+    pub fn set_on_complete(
+        &self,
+        on_complete: impl FnOnce(InvokeOutcome, &ipc::InvokeResponse) + Send + 'static,
+    ) {
+        if let Ok(Ok(request)) = self.inner.try_lock_inner_ref() {
+            *request
+                .resolver
+                .on_complete
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner) = Some(Box::new(on_complete));
+        }
+    }
+
+    /// Takes the resolver back from this [Invoke],
+    /// if it has not been consumed (e.g., by [Invoke::bind_to]) yet.
+    ///
+    /// This is useful for rejecting the ipc when the Python side fails to handle it.
+    #[cfg(feature = "__private")]
+    pub fn try_take_resolver(&self) -> Option<InvokeResponder> {
+        match self.inner.try_take_inner() {
+            Ok(Ok(request)) => request.resolver.try_take().ok(),
+            // - `ConsumedError`: the Python side has already taken the ownership
+            // - `LockError`: the Python side is using it now
+            Ok(Err(_)) | Err(_) => None,
+        }
     }
 
     const PYFUNC_HEADER_KEY: &str = "pyfunc";
 
+    /// Gets the command name from the `pyfunc` header of the ipc message, see [Invoke::new].
+    #[cfg(feature = "__private")]
+    pub fn header_command(message: &InvokeMessage<Runtime>) -> Result<&str, String> {
+        Self::get_func_name_from_message(message)
+    }
+
     #[inline]
     fn get_func_name_from_message(message: &InvokeMessage<Runtime>) -> Result<&str, String> {
         let func_name = message
@@ -137,25 +616,71 @@ impl Invoke {
     }
 }
 
+/// The name of `tauri-plugin-pytauri`, for resolving the scopes of the ipc.
+const PYTAURI_PLUGIN_NAME: &str = "pytauri";
+
+/// The origin of the `url`, e.g., `https://example.com:8080`, `tauri://localhost`.
+///
+/// Unlike [tauri::Url::origin], the origin of custom protocols (e.g., `tauri://localhost`)
+/// is not opaque (`null`), because it is how tauri serves the local assets.
+fn url_origin(url: &TauriUrl) -> String {
+    let origin = url.origin();
+    if origin.is_tuple() {
+        return origin.ascii_serialization();
+    }
+    match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{}://{host}:{port}", url.scheme()),
+        (Some(host), None) => format!("{}://{host}", url.scheme()),
+        (None, _) => origin.ascii_serialization(),
+    }
+}
+
+fn scope_to_json(
+    allows: &[Arc<serde_json::Value>],
+    denies: &[Arc<serde_json::Value>],
+) -> serde_json::Value {
+    let to_json = |scopes: &[Arc<serde_json::Value>]| {
+        scopes
+            .iter()
+            .map(|scope| scope.as_ref().clone())
+            .collect::<Vec<_>>()
+    };
+    serde_json::json!({ "allow": to_json(allows), "deny": to_json(denies) })
+}
+
 #[pymethods]
 // NOTE: These pymethods implementation must not block
 impl Invoke {
     // NOTE: remember to use `pyo3::intern!` for performance,
     // see: <https://github.com/PyO3/pyo3/discussions/2266#discussioncomment-2491646>.
     const BODY_KEY: &str = "body";
+    const BODY_FORMAT_KEY: &str = "body_format";
     const APP_HANDLE_KEY: &str = "app_handle";
     const WEBVIEW_WINDOW_KEY: &str = "webview_window";
     const HEADERS_KEY: &str = "headers";
     const STATES_KEY: &str = "states";
+    const WEBVIEW_KEY: &str = "webview";
+    const WINDOW_KEY: &str = "window";
+    const URL_KEY: &str = "url";
+    const ORIGIN_KEY: &str = "origin";
+    const COMMAND_SCOPE_KEY: &str = "command_scope";
+    const GLOBAL_SCOPE_KEY: &str = "global_scope";
 
     /// Pass in a Python dictionary, which can contain the following
     /// optional keys:
     ///
-    /// - [Self::BODY_KEY] : [PyBytes]
+    /// - [Self::BODY_KEY] : [PyBytes] or `Any`, depending on [Self::BODY_FORMAT_KEY]
+    /// - [Self::BODY_FORMAT_KEY] : see [BodyFormat], it's an input-only key
     /// - [Self::APP_HANDLE_KEY] : [crate::ext_mod::AppHandle]
     /// - [Self::WEBVIEW_WINDOW_KEY] : [crate::ext_mod::webview::WebviewWindow]
-    /// - [Self::HEADERS_KEY] : `list[tuple[bytes, bytes]]`
+    /// - [Self::HEADERS_KEY] : [Headers]
     /// - [Self::STATES_KEY] : `dict[str, type[Any]]`
+    /// - [Self::WEBVIEW_KEY] : [crate::ext_mod::webview::Webview]
+    /// - [Self::WINDOW_KEY] : [crate::ext_mod::window::Window]
+    /// - [Self::URL_KEY] : `str`, the current URL of the webview
+    /// - [Self::ORIGIN_KEY] : `str`, see [url_origin]
+    /// - [Self::COMMAND_SCOPE_KEY] : `{"allow": list[Any], "deny": list[Any]}`, see [tauri::ipc::CommandScope]
+    /// - [Self::GLOBAL_SCOPE_KEY] : `{"allow": list[Any], "deny": list[Any]}`, see [tauri::ipc::GlobalScope]
     ///
     /// # Returns
     ///
@@ -172,27 +697,50 @@ impl Invoke {
         // for how to parse the arguments
 
         let py = parameters.py();
-        let invoke = self.inner.try_take_inner()??;
-        let IpcInvoke {
+
+        // NOTE: check it before consuming [Invoke], because it's the programming error of the Python side.
+        let body_format = match parameters.get_item(intern!(py, Invoke::BODY_FORMAT_KEY))? {
+            Some(body_format) => BodyFormat::extract(&body_format)?,
+            None => BodyFormat::default(),
+        };
+
+        let request = self.inner.try_take_inner()??;
+        let InvokeRequest {
             message,
-            resolver,
             acl,
-        } = invoke;
+            resolver: pending_resolver,
+        } = request;
+        // NOTE: take the resolver only when we need to reject the ipc,
+        // otherwise it will be shared with the returned [InvokeResolver].
 
         let arguments = PyDict::new(py);
 
         let body_key = intern!(py, Invoke::BODY_KEY);
         if parameters.contains(body_key)? {
-            match message.payload() {
-                InvokeBody::Json(_) => {
-                    resolver.reject(
-                        "Please use `ArrayBuffer` or `Uint8Array` for `InvokeBody::Raw`. \
-                        If you are using `pyInvoke`, please report this as bug to pytauri developers.",
-                    );
-                    return Ok(None);
+            let py_body = match (message.payload(), body_format) {
+                (InvokeBody::Raw(body), BodyFormat::Bytes) => PyBytes::new(py, body).into_any(),
+                // NOTE: `pyInvoke` always uses `InvokeBody::Raw`,
+                // this is for the plain `invoke()` of `@tauri-apps/api`.
+                (InvokeBody::Json(body), BodyFormat::Bytes) => {
+                    // PERF: we have to serialize it again, because tauri has already deserialized it.
+                    let body = serde_json::to_vec(body)
+                        .expect("serializing `serde_json::Value` to bytes should never fail");
+                    PyBytes::new(py, &body).into_any()
                 }
-                InvokeBody::Raw(body) => arguments.set_item(body_key, PyBytes::new(py, body))?,
-            }
+                (InvokeBody::Json(body), BodyFormat::Object) => PySerde::new(body).to_object(py)?,
+                (InvokeBody::Raw(body), BodyFormat::Object) => {
+                    match serde_json::from_slice::<serde_json::Value>(body) {
+                        Ok(body) => PySerde::new(body).to_object(py)?,
+                        Err(e) => {
+                            pending_resolver
+                                .try_take()?
+                                .reject(format!("Failed to deserialize the body as JSON: {e}"));
+                            return Ok(None);
+                        }
+                    }
+                }
+            };
+            arguments.set_item(body_key, py_body)?;
         }
 
         let app_handle_key = intern!(py, Invoke::APP_HANDLE_KEY);
@@ -215,37 +763,94 @@ impl Invoke {
             {
                 Ok(webview_window) => webview_window,
                 Err(e) => {
-                    resolver.invoke_error(e);
+                    pending_resolver.try_take()?.invoke_error(e);
                     return Ok(None);
                 }
             };
             arguments.set_item(webview_window_key, WebviewWindow::new(webview_window))?;
         }
 
+        let webview_key = intern!(py, Invoke::WEBVIEW_KEY);
+        if parameters.contains(webview_key)? {
+            arguments.set_item(webview_key, Webview::new(message.webview()))?;
+        }
+
+        let window_key = intern!(py, Invoke::WINDOW_KEY);
+        if parameters.contains(window_key)? {
+            arguments.set_item(window_key, Window::new(message.webview_ref().window()))?;
+        }
+
+        let url_key = intern!(py, Invoke::URL_KEY);
+        let origin_key = intern!(py, Invoke::ORIGIN_KEY);
+        let need_url = parameters.contains(url_key)?;
+        let need_origin = parameters.contains(origin_key)?;
+        if need_url || need_origin {
+            // NOTE: [tauri::Webview::url] may need the main thread, so we release the GIL
+            let url = match py.allow_threads(|| message.webview_ref().url()) {
+                Ok(url) => url,
+                Err(e) => {
+                    pending_resolver.try_take()?.reject(e.to_string());
+                    return Ok(None);
+                }
+            };
+            if need_origin {
+                arguments.set_item(origin_key, url_origin(&url))?;
+            }
+            if need_url {
+                arguments.set_item(url_key, Url::from(url))?;
+            }
+        }
+
+        let command_scope_key = intern!(py, Invoke::COMMAND_SCOPE_KEY);
+        if parameters.contains(command_scope_key)? {
+            let item = CommandItem {
+                plugin: Some(PYTAURI_PLUGIN_NAME),
+                name: "__whatever__pyfunc",
+                key: "__whatever__commandScope",
+                message: &message,
+                acl: &acl,
+            };
+            let scope = match CommandScope::<serde_json::Value>::from_command(item) {
+                Ok(scope) => scope,
+                Err(e) => {
+                    pending_resolver.try_take()?.invoke_error(e);
+                    return Ok(None);
+                }
+            };
+            let scope = scope_to_json(scope.allows(), scope.denies());
+            arguments.set_item(command_scope_key, PySerde::new(scope).to_object(py)?)?;
+        }
+
+        let global_scope_key = intern!(py, Invoke::GLOBAL_SCOPE_KEY);
+        if parameters.contains(global_scope_key)? {
+            let item = CommandItem {
+                plugin: Some(PYTAURI_PLUGIN_NAME),
+                name: "__whatever__pyfunc",
+                key: "__whatever__globalScope",
+                message: &message,
+                acl: &acl,
+            };
+            let scope = match GlobalScope::<serde_json::Value>::from_command(item) {
+                Ok(scope) => scope,
+                Err(e) => {
+                    pending_resolver.try_take()?.invoke_error(e);
+                    return Ok(None);
+                }
+            };
+            let scope = scope_to_json(scope.allows(), scope.denies());
+            arguments.set_item(global_scope_key, PySerde::new(scope).to_object(py)?)?;
+        }
+
         let headers_key = intern!(py, Invoke::HEADERS_KEY);
         if parameters.contains(headers_key)? {
-            let headers: Vec<(&[u8], &[u8])> = message
-                .headers()
-                // PERF:
-                // > Each key will be yielded once per associated value.
-                // > So, if a key has 3 associated values, it will be yielded 3 times.
-                //
-                // This means the same key may generate multiple PyBytes objects
-                // (although this is consistent with the Python `h11` implementation).
-                // We need to use [HeaderMap::into_iter] to improve this (but this requires ownership, need a Tauri feature request):
-                // when get [None], we only need to clone the previous PyBytes.
-                .iter()
-                // PERF: Perhaps we don't need to filter out [PYFUNC_HEADER_KEY], just pass it to Python as is.
-                //
-                // TODO: Ideally, we should use [HeaderMap::remove] in [Self::get_func_name_from_message]
-                // to pop [PYFUNC_HEADER_KEY], but currently, we cannot obtain ownership/mutable reference
-                // of `headers` from `invoke`. We should submit a feature request to Tauri.
-                .filter(|(key, _)| **key != Self::PYFUNC_HEADER_KEY)
-                .map(|(key, value)| (key.as_ref(), value.as_bytes()))
-                .collect();
-            // TODO: Unify and export this type in [crate::ext_mod::ipc], see Python [pytauri.ipc.Headers] type.
-            let py_headers = PyList::new(py, headers)?;
-            arguments.set_item(headers_key, py_headers)?;
+            // NOTE: cloning [HeaderMap] is cheap (refcounted), and the headers are
+            // converted to Python objects lazily, see [Headers].
+            let mut headers = message.headers().clone();
+            // TODO: Ideally, we should use [HeaderMap::remove] in [Self::get_func_name_from_message]
+            // to pop [PYFUNC_HEADER_KEY], but currently, we cannot obtain ownership/mutable reference
+            // of `headers` from `invoke`. We should submit a feature request to Tauri.
+            headers.remove(Self::PYFUNC_HEADER_KEY);
+            arguments.set_item(headers_key, Headers(headers))?;
         }
 
         let states_key = intern!(py, Invoke::STATES_KEY);
@@ -261,7 +866,7 @@ impl Invoke {
                 if let Some(state) = state_manager.try_state(py, state_type)? {
                     states_args.set_item(key, state)?;
                 } else {
-                    resolver.reject(format!(
+                    pending_resolver.try_take()?.reject(format!(
                         "state `{state_type}` not managed for field `{key}`. \
                         You must call `.manage()` before using this command"
                     ));
@@ -271,34 +876,196 @@ impl Invoke {
             arguments.set_item(states_key, states_args)?;
         }
 
-        Ok(Some(InvokeResolver::new(resolver, arguments.unbind())))
+        Ok(Some(InvokeResolver::new(
+            pending_resolver,
+            arguments.unbind(),
+        )))
     }
 
     fn resolve(&self, py: Python<'_>, value: InvokeResponseBody) -> PyResult<()> {
         // NOTE: This function implementation must not block
 
         py.allow_threads(|| {
-            let resolver = self.inner.try_take_inner()??.resolver;
-            resolver.resolve(TauriInvokeResponseBody::from(value));
+            let resolver = self.inner.try_take_inner()??.resolver.try_take()?;
+            resolver.resolve(value.to_tauri());
             Ok(())
         })
     }
 
-    // TODO: Support more Python types. Tauri seems to only support `serde` types,
-    // and not support `Raw: Vec<[u8]>`. We should open an issue to ask them about this.
-    //
-    // TODO, PERF: once we drop py39, we can use `value: &str` instead of `Cow<'_, str>`.
-    fn reject(&self, py: Python<'_>, value: Cow<'_, str>) -> PyResult<()> {
+    // NOTE: Tauri only supports `serde` types for rejection, not `Raw: Vec<[u8]>`.
+    #[pyo3(signature = (value, /, *, json = false))]
+    fn reject(&self, value: &Bound<'_, PyAny>, json: bool) -> PyResult<()> {
         // NOTE: This function implementation must not block
 
-        py.allow_threads(|| {
-            let resolver = self.inner.try_take_inner()??.resolver;
+        let rejection = InvokeRejection::extract(value, json)?;
+        value.py().allow_threads(|| {
+            let resolver = self.inner.try_take_inner()??.resolver.try_take()?;
+            rejection.reject(resolver);
+            Ok(())
+        })
+    }
+
+    #[pyo3(signature = (exception, /, *, traceback = None))]
+    fn reject_exception(
+        &self,
+        exception: &Bound<'_, PyBaseException>,
+        traceback: Option<bool>,
+    ) -> PyResult<()> {
+        // NOTE: This function implementation must not block
+
+        let value = exception_to_json(exception, traceback.unwrap_or(IS_DEV));
+        exception.py().allow_threads(|| {
+            let resolver = self.inner.try_take_inner()??.resolver.try_take()?;
             resolver.reject(value);
             Ok(())
         })
     }
 }
 
+/// See also: [tauri::http::HeaderMap]
+///
+/// The header names are case-insensitive, and can be `str` or `bytes`.
+/// The header values are converted to `bytes` only when they are accessed.
+#[pyclass(frozen)]
+#[non_exhaustive]
+pub struct Headers(pub HeaderMap);
+
+impl Headers {
+    /// Returns [None] if `key` is not a valid header name,
+    /// which means it's impossible to be in the [HeaderMap].
+    fn header_name(key: &Bound<'_, PyAny>) -> PyResult<Option<HeaderName>> {
+        let name = if let Ok(key) = key.downcast::<PyString>() {
+            HeaderName::from_bytes(key.to_cow()?.as_bytes())
+        } else {
+            HeaderName::from_bytes(key.extract::<&[u8]>()?)
+        };
+        Ok(name.ok())
+    }
+
+    fn values<'py>(
+        py: Python<'py>,
+        values: GetAll<'_, HeaderValue>,
+    ) -> PyResult<Bound<'py, PyList>> {
+        let values = values.iter().map(HeaderValue::as_bytes).collect::<Vec<_>>();
+        PyList::new(py, values)
+    }
+
+    fn header_value(value: &Bound<'_, PyAny>) -> PyResult<HeaderValue> {
+        let value = if let Ok(value) = value.downcast::<PyString>() {
+            HeaderValue::from_str(&value.to_cow()?)
+        } else {
+            HeaderValue::from_bytes(value.extract::<&[u8]>()?)
+        };
+        value.map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+}
+
+#[pymethods]
+impl Headers {
+    /// `headers` can be a `Mapping` or an `Iterable` of `(key, value)` pairs.
+    #[new]
+    #[pyo3(signature = (headers = None, /))]
+    fn __new__(headers: Option<&Bound<'_, PyAny>>) -> PyResult<Self> {
+        let mut map = HeaderMap::new();
+        let Some(headers) = headers else {
+            return Ok(Self(map));
+        };
+        let items = if headers.hasattr(intern!(headers.py(), "items"))? {
+            headers.call_method0(intern!(headers.py(), "items"))?
+        } else {
+            headers.clone()
+        };
+        for item in items.try_iter()? {
+            let (key, value): (Bound<'_, PyAny>, Bound<'_, PyAny>) = item?.extract()?;
+            let name = Self::header_name(&key)?
+                .ok_or_else(|| PyValueError::new_err(format!("invalid header name: {key}")))?;
+            map.append(name, Self::header_value(&value)?);
+        }
+        Ok(Self(map))
+    }
+
+    /// Returns the first value of `key`, or `default` if there is no such header.
+    #[pyo3(signature = (key, default = None, /))]
+    fn get(
+        &self,
+        py: Python<'_>,
+        key: &Bound<'_, PyAny>,
+        default: Option<PyObject>,
+    ) -> PyResult<PyObject> {
+        let value = Self::header_name(key)?.and_then(|name| self.0.get(name));
+        match value {
+            Some(value) => Ok(PyBytes::new(py, value.as_bytes()).into_any().unbind()),
+            None => Ok(default.unwrap_or_else(|| py.None())),
+        }
+    }
+
+    /// Returns all values of `key`, in the order they were added.
+    fn get_all<'py>(
+        &self,
+        py: Python<'py>,
+        key: &Bound<'_, PyAny>,
+    ) -> PyResult<Bound<'py, PyList>> {
+        let Some(name) = Self::header_name(key)? else {
+            return Ok(PyList::empty(py));
+        };
+        Self::values(py, self.0.get_all(name))
+    }
+
+    /// Returns `dict[bytes, list[bytes]]`.
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        for name in self.0.keys() {
+            let values = Self::values(py, self.0.get_all(name))?;
+            dict.set_item(PyBytes::new(py, name.as_ref()), values)?;
+        }
+        Ok(dict)
+    }
+
+    fn __contains__(&self, key: &Bound<'_, PyAny>) -> PyResult<bool> {
+        Ok(Self::header_name(key)?.is_some_and(|name| self.0.contains_key(name)))
+    }
+
+    /// The number of `(key, value)` pairs, not the number of keys.
+    fn __len__(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Yields `(key, value)` pairs, a key will be yielded once per associated value.
+    fn __iter__(&self) -> HeadersIter {
+        // NOTE: only clone the rust headers (which is cheap), not convert them to Python objects.
+        let items = self
+            .0
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect::<Vec<_>>();
+        HeadersIter(items.into_iter())
+    }
+}
+
+/// The iterator of [Headers].
+#[pyclass]
+#[non_exhaustive]
+pub struct HeadersIter(std::vec::IntoIter<(HeaderName, HeaderValue)>);
+
+#[pymethods]
+impl HeadersIter {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__<'py>(
+        &mut self,
+        py: Python<'py>,
+    ) -> Option<(Bound<'py, PyBytes>, Bound<'py, PyBytes>)> {
+        self.0.next().map(|(name, value)| {
+            (
+                PyBytes::new(py, name.as_ref()),
+                PyBytes::new(py, value.as_bytes()),
+            )
+        })
+    }
+}
+
 /// See also: [tauri::ipc::JavaScriptChannelId]
 #[pyclass(frozen)]
 #[non_exhaustive]
@@ -328,47 +1095,415 @@ impl JavaScriptChannelId {
         }
     }
 
-    /// PERF, TODO: maybe we should accept `Union[Webview, WebviewWindow]`,
-    /// so that user dont need create new `Webview` pyobject for `WebviewWindow`.
-    fn channel_on(&self, py: Python<'_>, webview: Py<Webview>) -> Channel {
+    #[pyo3(signature = (webview, /, *, max_queue = None))]
+    fn channel_on(
+        &self,
+        py: Python<'_>,
+        webview: ImplWebview,
+        max_queue: Option<NonZeroUsize>,
+    ) -> PyResult<Channel> {
         py.allow_threads(|| {
             let js_channel_id = self.0.inner_ref();
-            let webview = webview.get().0.inner_ref().clone();
+            let webview = webview.to_webview()?;
             // TODO, FIXME, PERF:
             // Why [JavaScriptChannelId::channel_on] need take the ownership of [Webview]?
             // We should ask tauri developers.
-            let channel = js_channel_id.channel_on(webview); // maybe block, so we release the GIL
-            Channel::new(channel)
+            let channel = js_channel_id.channel_on(webview.clone()); // maybe block, so we release the GIL
+            Ok(Channel::from_tauri(channel, Some(&webview), max_queue))
         })
     }
 }
 
+/// The objects that can be resolved to a [tauri::webview::Webview].
+#[derive(FromPyObject)]
+enum ImplWebview {
+    // NOTE: `WebviewWindow` is what `Invoke.bind_to` gives, so we put it first.
+    WebviewWindow(Py<WebviewWindow>),
+    Webview(Py<Webview>),
+    Window(Py<Window>),
+}
+
+impl ImplWebview {
+    /// For [ImplWebview::Window], it's the webview with the same label (i.e., `WebviewWindow`),
+    /// or the only webview of the window.
+    fn to_webview(&self) -> PyResult<TauriWebview> {
+        match self {
+            ImplWebview::WebviewWindow(webview_window) => {
+                Ok(webview_window.get().0.inner_ref().as_ref().clone())
+            }
+            ImplWebview::Webview(webview) => Ok(webview.get().0.inner_ref().clone()),
+            ImplWebview::Window(window) => {
+                let window = window.get().0.inner_ref();
+                let mut webviews = window.webviews();
+                if let Some(idx) = webviews
+                    .iter()
+                    .position(|webview| webview.label() == window.label())
+                {
+                    return Ok(webviews.swap_remove(idx));
+                }
+                match <[TauriWebview; 1]>::try_from(webviews) {
+                    Ok([webview]) => Ok(webview),
+                    Err(webviews) => Err(PyValueError::new_err(format!(
+                        "cannot determine the webview of window `{}`, it has {} webviews",
+                        window.label(),
+                        webviews.len()
+                    ))),
+                }
+            }
+        }
+    }
+}
+
+/// The lifecycle of a [Channel], it's closed when the JS side is gone,
+/// i.e., the webview navigates to a new page or is destroyed, see [close_channels].
+struct ChannelLifecycle {
+    closed: AtomicBool,
+    /// `None` means closed, so we don't need to lock it to check [Self::closed].
+    on_close: Mutex<Option<Vec<PyObject>>>,
+}
+
+impl ChannelLifecycle {
+    fn new() -> Arc<Self> {
+        Arc::new(Self {
+            closed: AtomicBool::new(false),
+            on_close: Mutex::new(Some(Vec::new())),
+        })
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    /// Returns the `on_close` callbacks if it was not closed.
+    fn close(&self) -> Vec<PyObject> {
+        let callbacks = self
+            .on_close
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        self.closed.store(true, Ordering::Release);
+        callbacks.unwrap_or_default()
+    }
+}
+
+/// The [ChannelLifecycle]s of the webviews, which is managed by the tauri app.
+#[derive(Default)]
+struct ChannelLifecycles(Mutex<Vec<ChannelEntry>>);
+
+struct ChannelEntry {
+    webview: String,
+    window: String,
+    lifecycle: Weak<ChannelLifecycle>,
+}
+
+impl ChannelLifecycles {
+    fn register(webview: &TauriWebview, lifecycle: &Arc<ChannelLifecycle>) {
+        // NOTE: if `false`, it's already managed (maybe by other threads at the same time)
+        let _ = webview.manage(Self::default());
+        let mut entries = webview
+            .state::<Self>()
+            .inner()
+            .0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        entries.retain(|entry| entry.lifecycle.strong_count() > 0);
+        entries.push(ChannelEntry {
+            webview: webview.label().to_owned(),
+            window: webview.window_ref().label().to_owned(),
+            lifecycle: Arc::downgrade(lifecycle),
+        });
+    }
+
+    fn close_if(
+        manager: &impl Manager<Runtime>,
+        predicate: impl Fn(&ChannelEntry) -> bool,
+    ) -> Vec<PyObject> {
+        let Some(this) = manager.try_state::<Self>() else {
+            // no channel has been created
+            return Vec::new();
+        };
+        let mut closed = Vec::new();
+        this.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|entry| {
+                if !predicate(entry) {
+                    return entry.lifecycle.strong_count() > 0;
+                }
+                if let Some(lifecycle) = entry.lifecycle.upgrade() {
+                    closed.extend(lifecycle.close());
+                }
+                false
+            });
+        closed
+    }
+}
+
+/// The event that closes [Channel]s, see [close_channels].
+pub enum ChannelCloseEvent<'a> {
+    /// The webview with this label starts loading a new page.
+    PageLoad(&'a str),
+    /// The window with this label (and all its webviews) is destroyed.
+    WindowDestroyed(&'a str),
+}
+
+/// Marks the [Channel]s affected by `event` as closed.
+///
+/// Returns the `on_close` callbacks which should be called by [call_channel_on_close].
+pub fn close_channels(
+    manager: &impl Manager<Runtime>,
+    event: ChannelCloseEvent<'_>,
+) -> Vec<PyObject> {
+    match event {
+        ChannelCloseEvent::PageLoad(label) => {
+            ChannelLifecycles::close_if(manager, |entry| entry.webview == label)
+        }
+        ChannelCloseEvent::WindowDestroyed(label) => {
+            ChannelLifecycles::close_if(manager, |entry| entry.window == label)
+        }
+    }
+}
+
+/// Calls the `on_close` callbacks returned by [close_channels].
+///
+/// The exceptions raised by the callbacks are written to `sys.unraisablehook`.
+pub fn call_channel_on_close(py: Python<'_>, callbacks: Vec<PyObject>) {
+    for callback in callbacks {
+        let callback = callback.bind(py);
+        if let Err(e) = callback.call0() {
+            e.write_unraisable(py, Some(callback));
+        }
+    }
+}
+
+/// The error of [ChannelQueue::send].
+enum ChannelQueueError {
+    Full,
+    /// The worker has exited, i.e., the channel is closed.
+    Closed,
+}
+
+/// Wakes up the senders blocked by the full [ChannelQueue].
+#[derive(Default)]
+struct ChannelQueueSpace {
+    lock: Mutex<()>,
+    available: Condvar,
+}
+
+impl ChannelQueueSpace {
+    fn notify(&self) {
+        // NOTE: notify with the lock held, so the senders will not miss it,
+        // see [ChannelQueue::send].
+        let _guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        self.available.notify_all();
+    }
+}
+
+/// The bounded send queue of a [Channel], its worker is a task on [tauri::async_runtime],
+/// so it doesn't occupy a thread for each channel.
+struct ChannelQueue {
+    sender: tokio_mpsc::Sender<TauriInvokeResponseBody>,
+    space: Arc<ChannelQueueSpace>,
+}
+
+impl ChannelQueue {
+    /// Spawns the worker that sends the queued data in order.
+    ///
+    /// The worker exits when the [ChannelQueue] is dropped or the [Channel] is closed.
+    fn spawn(
+        channel: ipc::Channel,
+        lifecycle: Arc<ChannelLifecycle>,
+        max_queue: NonZeroUsize,
+    ) -> Self {
+        let (sender, mut receiver) =
+            tokio_mpsc::channel::<TauriInvokeResponseBody>(max_queue.get());
+        let space = Arc::new(ChannelQueueSpace::default());
+
+        let worker_space = space.clone();
+        tauri::async_runtime::spawn(async move {
+            while let Some(data) = receiver.recv().await {
+                worker_space.notify();
+                if lifecycle.is_closed() {
+                    break;
+                }
+                if channel.send(data).is_err() {
+                    // the webview is gone, so we close the channel
+                    let callbacks = lifecycle.close();
+                    if !callbacks.is_empty() {
+                        // NOTE: don't block the async runtime while acquiring the GIL
+                        tauri::async_runtime::spawn_blocking(move || {
+                            Python::with_gil(|py| call_channel_on_close(py, callbacks))
+                        });
+                    }
+                    break;
+                }
+            }
+            // wake up the blocked senders, they will find the queue closed
+            drop(receiver);
+            worker_space.notify();
+        });
+
+        Self { sender, space }
+    }
+
+    /// If `block`, blocks the current thread until there is free space in the queue.
+    ///
+    /// NOTE: unlike [tokio_mpsc::Sender::blocking_send], it can be called in the async runtime,
+    /// because the Python code may run anywhere.
+    fn send(
+        &self,
+        mut data: TauriInvokeResponseBody,
+        block: bool,
+    ) -> Result<(), ChannelQueueError> {
+        // NOTE: hold the lock between `try_send` and `wait`, so the `notify` can't be missed
+        let mut guard = self
+            .space
+            .lock
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        loop {
+            match self.sender.try_send(data) {
+                Ok(()) => return Ok(()),
+                Err(TrySendError::Closed(_)) => return Err(ChannelQueueError::Closed),
+                Err(TrySendError::Full(_)) if !block => return Err(ChannelQueueError::Full),
+                Err(TrySendError::Full(rejected)) => {
+                    data = rejected;
+                    guard = self
+                        .space
+                        .available
+                        .wait(guard)
+                        .unwrap_or_else(PoisonError::into_inner);
+                }
+            }
+        }
+    }
+}
+
 /// See also: [tauri::ipc::Channel]
 #[pyclass(frozen)]
 #[non_exhaustive]
-pub struct Channel(PyWrapper<PyWrapperT0<ipc::Channel>>);
+pub struct Channel {
+    inner: PyWrapper<PyWrapperT0<ipc::Channel>>,
+    lifecycle: Arc<ChannelLifecycle>,
+    /// The bounded send queue, see [ChannelQueue].
+    queue: Option<ChannelQueue>,
+}
+
+impl Channel {
+    /// If `webview` is [None], the channel is never closed, see [ChannelLifecycle].
+    fn from_tauri(
+        channel: ipc::Channel,
+        webview: Option<&TauriWebview>,
+        max_queue: Option<NonZeroUsize>,
+    ) -> Self {
+        let lifecycle = ChannelLifecycle::new();
+        if let Some(webview) = webview {
+            ChannelLifecycles::register(webview, &lifecycle);
+        }
+        let queue = max_queue
+            .map(|max_queue| ChannelQueue::spawn(channel.clone(), lifecycle.clone(), max_queue));
+        Self {
+            inner: PyWrapper::new0(channel),
+            lifecycle,
+            queue,
+        }
+    }
+
+    fn send_body(&self, data: TauriInvokeResponseBody, block: bool) -> PyResult<()> {
+        let Some(queue) = &self.queue else {
+            self.inner
+                .inner_ref()
+                .send(data)
+                .map_err(TauriError::from)?;
+            return Ok(());
+        };
+        match queue.send(data, block) {
+            Ok(()) => Ok(()),
+            Err(ChannelQueueError::Full) => Python::with_gil(|py| {
+                let full = py
+                    .import(intern!(py, "queue"))?
+                    .getattr(intern!(py, "Full"))?;
+                Err(PyErr::from_value(full.call0()?))
+            }),
+            Err(ChannelQueueError::Closed) => Err(PyRuntimeError::new_err("the channel is closed")),
+        }
+    }
+}
 
 impl Channel {
-    fn new(channel: ipc::Channel) -> Self {
-        Self(PyWrapper::new0(channel))
+    /// Gets the underlying [tauri::ipc::Channel], e.g., to pass it to a tauri plugin.
+    pub fn channel(&self) -> ipc::Channel {
+        self.inner.inner_ref().clone()
     }
 }
 
 #[pymethods]
 impl Channel {
+    /// Creates a new Rust-side channel, the data sent to it will be passed to `on_message`.
+    #[staticmethod]
+    #[pyo3(signature = (on_message, /, *, max_queue = None))]
+    fn new(on_message: PyObject, max_queue: Option<NonZeroUsize>) -> Self {
+        let channel = ipc::Channel::new(move |body| {
+            Python::with_gil(|py| {
+                let on_message = on_message.bind(py);
+                let result = match body {
+                    TauriInvokeResponseBody::Json(json) => on_message.call1((json,)),
+                    TauriInvokeResponseBody::Raw(raw) => {
+                        on_message.call1((PyBytes::new(py, &raw),))
+                    }
+                };
+                // NOTE: tauri can't carry the Python exception, and the sender usually
+                // ignores the error, so we report it here.
+                if let Err(e) = result {
+                    e.write_unraisable(py, Some(on_message));
+                }
+            });
+            Ok(())
+        });
+        Self::from_tauri(channel, None, max_queue)
+    }
+
     fn id(&self) -> u32 {
-        self.0.inner_ref().id()
+        self.inner.inner_ref().id()
     }
 
-    fn send(&self, py: Python<'_>, data: InvokeResponseBody) -> PyResult<()> {
+    fn is_closed(&self) -> bool {
+        self.lifecycle.is_closed()
+    }
+
+    fn on_close(&self, py: Python<'_>, callback: PyObject) {
+        let mut on_close = self
+            .lifecycle
+            .on_close
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        match on_close.as_mut() {
+            Some(callbacks) => callbacks.push(callback),
+            None => {
+                drop(on_close);
+                // already closed
+                call_channel_on_close(py, vec![callback]);
+            }
+        }
+    }
+
+    #[pyo3(signature = (data, /, *, block = true))]
+    fn send(&self, py: Python<'_>, data: InvokeResponseBody, block: bool) -> PyResult<()> {
         // [tauri::ipc::Channel::send] is not a very fast operation,
         // so we need to release the GIL
+        py.allow_threads(|| self.send_body(data.to_tauri(), block))
+    }
+
+    #[pyo3(signature = (data, /, *, block = true))]
+    fn send_many(
+        &self,
+        py: Python<'_>,
+        data: Vec<InvokeResponseBody>,
+        block: bool,
+    ) -> PyResult<()> {
         py.allow_threads(|| {
-            self.0
-                .inner_ref()
-                .send(TauriInvokeResponseBody::from(data))
-                .map_err(TauriError::from)?;
-            Ok(())
+            data.iter()
+                .try_for_each(|data| self.send_body(data.to_tauri(), block))
         })
     }
 }
@@ -35,9 +35,11 @@ impl App {
             debug_assert_app_handle_py_is_rs(&app_handle, _app_handle);
 
             Python::with_gil(|py| {
-                let py_run_event: RunEvent = RunEvent::from_tauri(py, run_event)
-                    // TODO: maybe we should only `write_unraisable` and log it instead of `panic` here?
-                    .expect("Failed to convert rust `RunEvent` to pyobject");
+                let Some(py_run_event) = RunEvent::from_tauri(py, run_event)
+                    .ok_or_log_unraisable(py, || "Failed to convert rust `RunEvent` to pyobject")
+                else {
+                    return;
+                };
 
                 let callback = callback.bind(py);
                 let result = callback.call1((py_app_handle, py_run_event));
@@ -140,10 +140,13 @@ impl AppHandle {
                     Python::with_gil(|py| {
                         let app_handle: &Py<Self> = &moved_slf;
                         debug_assert_app_handle_py_is_rs(app_handle, _app_handle);
-                        let tray_icon_event: TrayIconEvent =
-                            TrayIconEvent::from_tauri(py, &tray_icon_event)
-                                // TODO: maybe we should only `write_unraisable` and log it instead of `panic` here?
-                                .expect("Failed to convert rust `TrayIconEvent` to pyobject");
+                        let Some(tray_icon_event) = TrayIconEvent::from_tauri(py, &tray_icon_event)
+                            .ok_or_log_unraisable(py, || {
+                                "Failed to convert rust `TrayIconEvent` to pyobject"
+                            })
+                        else {
+                            return;
+                        };
 
                         let handler = handler.bind(py);
                         let result = handler.call1((app_handle, tray_icon_event));
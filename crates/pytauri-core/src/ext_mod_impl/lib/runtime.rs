@@ -3,7 +3,7 @@ use std::borrow::Cow;
 use pyo3::prelude::*;
 use pyo3_utils::serde::PySerde;
 
-use crate::utils::non_exhaustive_panic;
+use crate::utils::{into_pyobject_for_copy_ref, non_exhaustive_panic};
 
 /// See also: [tauri::Config]
 // TODO, PERF: use `&Config` to avoid clone,
@@ -45,6 +45,7 @@ macro_rules! theme_impl {
 }
 
 theme_impl!(Theme => : Light, Dark);
+into_pyobject_for_copy_ref!(Theme);
 
 macro_rules! user_attention_type_impl {
     ($ident:ident => : $($variant:ident),*) => {
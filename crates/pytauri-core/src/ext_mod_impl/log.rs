@@ -0,0 +1,9 @@
+use pyo3::prelude::*;
+use pyo3_utils::log::log_py_record;
+
+/// Forwards a Python `logging.LogRecord` to the Rust `log` crate,
+/// see [pyo3_utils::log::log_py_record].
+#[pyfunction]
+pub fn log_record(record: &Bound<'_, PyAny>) -> PyResult<()> {
+    log_py_record(record)
+}
@@ -1,6 +1,7 @@
 pub(crate) mod image;
 pub(crate) mod ipc;
 pub(crate) mod lib;
+pub(crate) mod log;
 pub(crate) mod menu;
 pub(crate) mod path;
 pub(crate) mod plugin;
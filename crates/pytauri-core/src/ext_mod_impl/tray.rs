@@ -102,10 +102,13 @@ impl TrayIcon {
                         // so we can directly use the same PyObject.
                         let tray_icon: &Py<Self> = &moved_slf;
                         debug_assert_eq!(tray_icon.get().0.inner_ref().id(), _tray_icon.id());
-                        let tray_icon_event: TrayIconEvent =
-                            TrayIconEvent::from_tauri(py, &tray_icon_event)
-                                // TODO: maybe we should only `write_unraisable` and log it instead of `panic` here?
-                                .expect("Failed to convert rust `TrayIconEvent` to pyobject");
+                        let Some(tray_icon_event) = TrayIconEvent::from_tauri(py, &tray_icon_event)
+                            .ok_or_log_unraisable(py, || {
+                                "Failed to convert rust `TrayIconEvent` to pyobject"
+                            })
+                        else {
+                            return;
+                        };
 
                         let handler = handler.bind(py);
                         let result = handler.call1((tray_icon, tray_icon_event));
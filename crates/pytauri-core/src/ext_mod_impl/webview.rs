@@ -1,19 +1,27 @@
-use std::{borrow::Cow, path::PathBuf};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex, PoisonError},
+};
 
 use pyo3::{
     prelude::*,
     pybacked::PyBackedStr,
-    types::{PyDict, PyString},
+    types::{PyDict, PyString, PyTuple},
 };
 use pyo3_utils::{
-    from_py_dict::{derive_from_py_dict, FromPyDict as _, NotRequired},
+    from_py_dict::{FromPyDict, IntoPyDict, NotRequired},
     py_wrapper::{PyWrapper, PyWrapperT0},
     serde::PySerde,
     ungil::UnsafeUngilExt,
 };
-use tauri::webview::{
-    self,
-    cookie::{self, time::OffsetDateTime},
+use tauri::{
+    webview::{
+        self,
+        cookie::{self, time::OffsetDateTime},
+    },
+    Manager as _,
 };
 
 use crate::{
@@ -26,7 +34,7 @@ use crate::{
         Position, Size, Theme, Url, UserAttentionType, WebviewEvent, WebviewUrl, WindowEvent,
     },
     tauri_runtime::Runtime,
-    utils::{cfg_impl, delegate_inner, PyResultExt as _, TauriError},
+    utils::{cfg_impl, delegate_inner, into_pyobject_for_copy_ref, PyResultExt as _, TauriError},
 };
 
 pub(crate) type TauriWebviewWindow = webview::WebviewWindow<Runtime>;
@@ -86,13 +94,23 @@ impl WebviewWindow {
         PyString::intern(py, webview_window.label())
     }
 
+    fn builder_args<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyDict>>> {
+        let webview_window = self.0.inner_ref();
+        BuilderArgsStore::get(py, &webview_window)
+            // return a copy, so the stored one can't be modified
+            .map(|args| args.bind(py).copy())
+            .transpose()
+    }
+
     fn on_window_event(&self, py: Python<'_>, handler: PyObject) {
         py.allow_threads(|| {
             self.0.inner_ref().on_window_event(move |window_event| {
                 Python::with_gil(|py| {
-                    let window_event: WindowEvent = WindowEvent::from_tauri(py, window_event)
-                        // TODO: maybe we should only `write_unraisable` and log it instead of `panic` here?
-                        .expect("Failed to convert `WindowEvent` to pyobject");
+                    let Some(window_event) = WindowEvent::from_tauri(py, window_event)
+                        .ok_or_log_unraisable(py, || "Failed to convert `WindowEvent` to pyobject")
+                    else {
+                        return;
+                    };
 
                     let handler = handler.bind(py);
                     let result = handler.call1((window_event,));
@@ -108,9 +126,13 @@ impl WebviewWindow {
         py.allow_threads(|| {
             self.0.inner_ref().on_webview_event(move |webview_event| {
                 Python::with_gil(|py| {
-                    let webview_event: WebviewEvent = WebviewEvent::from_tauri(py, webview_event)
-                        // TODO: maybe we should only `write_unraisable` and log it instead of `panic` here?
-                        .expect("Failed to convert `WebviewEvent` to pyobject");
+                    let Some(webview_event) = WebviewEvent::from_tauri(py, webview_event)
+                        .ok_or_log_unraisable(py, || {
+                            "Failed to convert `WebviewEvent` to pyobject"
+                        })
+                    else {
+                        return;
+                    };
 
                     let handler = handler.bind(py);
                     let result = handler.call1((webview_event,));
@@ -648,6 +670,17 @@ impl<'py> FromPyObject<'py> for Color {
     }
 }
 
+impl<'py> IntoPyObject<'py> for &Color {
+    type Target = PyTuple;
+    type Output = Bound<'py, Self::Target>;
+    type Error = PyErr;
+
+    fn into_pyobject(self, py: Python<'py>) -> Result<Self::Output, Self::Error> {
+        let webview::Color(r, g, b, a) = self.0;
+        (r, g, b, a).into_pyobject(py)
+    }
+}
+
 macro_rules! same_site_impl {
     ($ident:ident => : $( $(#[$meta:meta])* $variant:ident ),*) => {
         /// See also: [cookie::SameSite]
@@ -683,15 +716,7 @@ same_site_impl!(SameSite => : Strict, Lax, #[pyo3(name = "None_")] None);
 
 // NOTE: we need to implement this manually
 // because [NotRequired::into_py_with_none] requires `'&T: IntoPyObject`
-impl<'py> IntoPyObject<'py> for &SameSite {
-    type Error = <SameSite as IntoPyObject<'py>>::Error;
-    type Output = <SameSite as IntoPyObject<'py>>::Output;
-    type Target = <SameSite as IntoPyObject<'py>>::Target;
-
-    fn into_pyobject(self, py: Python<'py>) -> Result<Self::Output, Self::Error> {
-        <SameSite as IntoPyObject<'py>>::into_pyobject(*self, py)
-    }
-}
+into_pyobject_for_copy_ref!(SameSite);
 
 /// See also: [tauri::webview::Cookie]
 // ref:
@@ -700,41 +725,28 @@ impl<'py> IntoPyObject<'py> for &SameSite {
 //
 // TODO: [IntoPyObject] does not use `pyo3::intern`, we should file an issue to pyo3
 // TODO: Submit a feature request to pyo3 to add `#[pyo3(skip_if)]` for skipping certain fields
-#[derive(IntoPyObject, IntoPyObjectRef)]
+#[derive(IntoPyObject, IntoPyObjectRef, FromPyDict)]
 pub struct Cookie {
     key: Py<PyString>,
     value: Py<PyString>,
+    #[pyo3(default)]
     max_age: Option<i64>,
+    #[pyo3(default)]
     expires: Option<OffsetDateTime>,
+    #[pyo3(default)]
     path: Option<Py<PyString>>,
+    #[pyo3(default)]
     domain: Option<Py<PyString>>,
+    #[pyo3(default)]
     secure: Option<bool>,
+    #[pyo3(default)]
     httponly: Option<bool>,
+    #[pyo3(default)]
     samesite: Option<SameSite>,
+    #[pyo3(default)]
     partitioned: Option<bool>,
 }
 
-derive_from_py_dict!(Cookie {
-    key,
-    value,
-    #[pyo3(default)]
-    max_age,
-    #[pyo3(default)]
-    expires,
-    #[pyo3(default)]
-    path,
-    #[pyo3(default)]
-    domain,
-    #[pyo3(default)]
-    secure,
-    #[pyo3(default)]
-    httponly,
-    #[pyo3(default)]
-    samesite,
-    #[pyo3(default)]
-    partitioned,
-});
-
 impl<'py> FromPyObject<'py> for Cookie {
     fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
         let dict = ob.downcast::<PyDict>()?;
@@ -817,6 +829,19 @@ impl Cookie {
 
 /// See also: [tauri::webview::WebviewWindowBuilder]
 #[non_exhaustive]
+#[derive(FromPyDict, IntoPyDict)]
+// accept the keys of the platform-specific fields on the other platforms,
+// keep it in sync with the `#[cfg(...)]` fields below.
+#[pyo3(ignore_keys(
+    "owner",
+    "transient_for",
+    "drag_and_drop",
+    "title_bar_style",
+    "traffic_light_position",
+    "allow_link_preview",
+    "hidden_title",
+    "tabbing_identifier"
+))]
 pub struct WebviewWindowBuilderArgs {
     // TODO, FIXME: on_menu_event: NotRequired<PyObject>,
     // `on_menu_event` passes `Window` instead of `WebviewWindow`,
@@ -824,41 +849,73 @@ pub struct WebviewWindowBuilderArgs {
     // or add a method to get `WebviewWindow` from `Window`
 
     // TODO: on_web_resource_request
+    #[pyo3(default)]
     on_navigation: NotRequired<PyObject>,
     // TODO: on_new_window
+    #[pyo3(default)]
     on_document_title_changed: NotRequired<PyObject>,
     // TODO: on_download, on_page_load
+    #[pyo3(default)]
     menu: NotRequired<Py<Menu>>,
+    #[pyo3(default)]
     center: NotRequired<bool>,
+    #[pyo3(default)]
     position: NotRequired<(f64, f64)>,
+    #[pyo3(default)]
     inner_size: NotRequired<(f64, f64)>,
+    #[pyo3(default)]
     min_inner_size: NotRequired<(f64, f64)>,
+    #[pyo3(default)]
     max_inner_size: NotRequired<(f64, f64)>,
     // TODO: inner_size_constraints
+    #[pyo3(default)]
     prevent_overflow: NotRequired<bool>,
+    #[pyo3(default)]
     prevent_overflow_with_margin: NotRequired<Py<Size>>,
+    #[pyo3(default)]
     resizable: NotRequired<bool>,
+    #[pyo3(default)]
     maximizable: NotRequired<bool>,
+    #[pyo3(default)]
     minimizable: NotRequired<bool>,
+    #[pyo3(default)]
     closable: NotRequired<bool>,
+    #[pyo3(default)]
     title: NotRequired<String>,
+    #[pyo3(default)]
     fullscreen: NotRequired<bool>,
+    #[pyo3(default)]
     focusable: NotRequired<bool>,
+    #[pyo3(default)]
     focused: NotRequired<bool>,
+    #[pyo3(default)]
     maximized: NotRequired<bool>,
+    #[pyo3(default)]
     visible: NotRequired<bool>,
+    #[pyo3(default)]
     theme: NotRequired<Option<Theme>>,
+    #[pyo3(default)]
     decorations: NotRequired<bool>,
+    #[pyo3(default)]
     always_on_bottom: NotRequired<bool>,
+    #[pyo3(default)]
     always_on_top: NotRequired<bool>,
+    #[pyo3(default)]
     visible_on_all_workspaces: NotRequired<bool>,
+    #[pyo3(default)]
     content_protected: NotRequired<bool>,
+    #[pyo3(default)]
     icon: NotRequired<Py<Image>>,
+    #[pyo3(default)]
     skip_taskbar: NotRequired<bool>,
+    #[pyo3(default)]
     window_classname: NotRequired<String>,
+    #[pyo3(default)]
     shadow: NotRequired<bool>,
+    #[pyo3(default)]
     parent: NotRequired<Py<WebviewWindow>>,
     #[cfg(windows)]
+    #[pyo3(default)]
     owner: NotRequired<Py<WebviewWindow>>,
     #[cfg(any(
         target_os = "linux",
@@ -867,185 +924,85 @@ pub struct WebviewWindowBuilderArgs {
         target_os = "netbsd",
         target_os = "openbsd"
     ))]
+    #[pyo3(default)]
     transient_for: NotRequired<Py<WebviewWindow>>,
     #[cfg(windows)]
+    #[pyo3(default)]
     drag_and_drop: NotRequired<bool>,
     #[cfg(target_os = "macos")]
+    #[pyo3(default)]
     title_bar_style: NotRequired<TitleBarStyle>,
     #[cfg(target_os = "macos")]
+    #[pyo3(default)]
     traffic_light_position: NotRequired<Py<Position>>,
     #[cfg(target_os = "macos")]
+    #[pyo3(default)]
     allow_link_preview: NotRequired<bool>,
     #[cfg(target_os = "macos")]
+    #[pyo3(default)]
     hidden_title: NotRequired<bool>,
     #[cfg(target_os = "macos")]
+    #[pyo3(default)]
     tabbing_identifier: NotRequired<PyBackedStr>,
+    #[pyo3(default)]
     effects: NotRequired<Effects>,
+    #[pyo3(default)]
     accept_first_mouse: NotRequired<bool>,
+    #[pyo3(default)]
     initialization_script: NotRequired<String>,
+    #[pyo3(default)]
     initialization_script_for_all_frames: NotRequired<String>,
+    #[pyo3(default)]
     user_agent: NotRequired<PyBackedStr>,
+    #[pyo3(default)]
     additional_browser_args: NotRequired<PyBackedStr>,
+    #[pyo3(default)]
     data_directory: NotRequired<PathBuf>,
+    #[pyo3(default)]
     disable_drag_drop_handler: NotRequired<bool>,
+    #[pyo3(default)]
     enable_clipboard_access: NotRequired<bool>,
+    #[pyo3(default)]
     incognito: NotRequired<bool>,
+    #[pyo3(default)]
     auto_resize: NotRequired<bool>,
     // TODO, PERF: remove `'static` bound
+    #[pyo3(default)]
     proxy_url: NotRequired<Url<'static>>,
+    #[pyo3(default)]
     transparent: NotRequired<bool>,
+    #[pyo3(default)]
     zoom_hotkeys_enabled: NotRequired<bool>,
+    #[pyo3(default)]
     browser_extensions_enabled: NotRequired<bool>,
+    #[pyo3(default)]
     extensions_path: NotRequired<PathBuf>,
     // TODO: data_store_identifier
+    #[pyo3(default)]
     use_https_scheme: NotRequired<bool>,
+    #[pyo3(default)]
     devtools: NotRequired<bool>,
+    #[pyo3(default)]
     background_color: NotRequired<Color>,
     // TODO: BackgroundThrottlingPolicy
+    #[pyo3(default)]
     disable_javascript: NotRequired<bool>,
     // TODO: window_features
 }
 
-derive_from_py_dict!(WebviewWindowBuilderArgs {
-    #[pyo3(default)]
-    on_navigation,
-    #[pyo3(default)]
-    on_document_title_changed,
-    #[pyo3(default)]
-    menu,
-    #[pyo3(default)]
-    center,
-    #[pyo3(default)]
-    position,
-    #[pyo3(default)]
-    inner_size,
-    #[pyo3(default)]
-    min_inner_size,
-    #[pyo3(default)]
-    max_inner_size,
-    #[pyo3(default)]
-    prevent_overflow,
-    #[pyo3(default)]
-    prevent_overflow_with_margin,
-    #[pyo3(default)]
-    resizable,
-    #[pyo3(default)]
-    maximizable,
-    #[pyo3(default)]
-    minimizable,
-    #[pyo3(default)]
-    closable,
-    #[pyo3(default)]
-    title,
-    #[pyo3(default)]
-    fullscreen,
-    #[pyo3(default)]
-    focusable,
-    #[pyo3(default)]
-    focused,
-    #[pyo3(default)]
-    maximized,
-    #[pyo3(default)]
-    visible,
-    #[pyo3(default)]
-    theme,
-    #[pyo3(default)]
-    decorations,
-    #[pyo3(default)]
-    always_on_bottom,
-    #[pyo3(default)]
-    always_on_top,
-    #[pyo3(default)]
-    visible_on_all_workspaces,
-    #[pyo3(default)]
-    content_protected,
-    #[pyo3(default)]
-    icon,
-    #[pyo3(default)]
-    skip_taskbar,
-    #[pyo3(default)]
-    window_classname,
-    #[pyo3(default)]
-    shadow,
-    #[pyo3(default)]
-    parent,
-    #[cfg(windows)]
-    #[pyo3(default)]
-    owner,
-    #[cfg(any(
-        target_os = "linux",
-        target_os = "dragonfly",
-        target_os = "freebsd",
-        target_os = "netbsd",
-        target_os = "openbsd"
-    ))]
-    #[pyo3(default)]
-    transient_for,
-    #[cfg(windows)]
-    #[pyo3(default)]
-    drag_and_drop,
-    #[cfg(target_os = "macos")]
-    #[pyo3(default)]
-    title_bar_style,
-    #[cfg(target_os = "macos")]
-    #[pyo3(default)]
-    traffic_light_position,
-    #[cfg(target_os = "macos")]
-    #[pyo3(default)]
-    allow_link_preview,
-    #[cfg(target_os = "macos")]
-    #[pyo3(default)]
-    hidden_title,
-    #[cfg(target_os = "macos")]
-    #[pyo3(default)]
-    tabbing_identifier,
-    #[pyo3(default)]
-    effects,
-    #[pyo3(default)]
-    accept_first_mouse,
-    #[pyo3(default)]
-    initialization_script,
-    #[pyo3(default)]
-    initialization_script_for_all_frames,
-    #[pyo3(default)]
-    user_agent,
-    #[pyo3(default)]
-    additional_browser_args,
-    #[pyo3(default)]
-    data_directory,
-    #[pyo3(default)]
-    disable_drag_drop_handler,
-    #[pyo3(default)]
-    enable_clipboard_access,
-    #[pyo3(default)]
-    incognito,
-    #[pyo3(default)]
-    auto_resize,
-    #[pyo3(default)]
-    proxy_url,
-    #[pyo3(default)]
-    transparent,
-    #[pyo3(default)]
-    zoom_hotkeys_enabled,
-    #[pyo3(default)]
-    browser_extensions_enabled,
-    #[pyo3(default)]
-    extensions_path,
-    #[pyo3(default)]
-    use_https_scheme,
-    #[pyo3(default)]
-    devtools,
-    #[pyo3(default)]
-    background_color,
-    #[pyo3(default)]
-    disable_javascript,
-});
-
 impl WebviewWindowBuilderArgs {
     fn from_kwargs(kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<Option<Self>> {
         kwargs.map(Self::from_py_dict).transpose()
     }
 
+    /// The reverse of [WebviewWindowBuilderArgs::from_kwargs].
+    fn to_kwargs<'py>(args: Option<&Self>, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        match args {
+            Some(args) => args.to_py_dict(py),
+            None => Ok(PyDict::new(py)),
+        }
+    }
+
     fn apply_to_builder<'a, M>(
         self,
         py: Python<'_>,
@@ -1372,6 +1329,56 @@ impl WebviewWindowBuilderArgs {
     }
 }
 
+/// The kwargs of the webview windows built by [WebviewWindowBuilder], which is managed by the tauri app.
+#[derive(Default)]
+struct BuilderArgsStore(Mutex<HashMap<String, Arc<Py<PyDict>>>>);
+
+impl BuilderArgsStore {
+    /// Stores `args` until `webview_window` is destroyed.
+    fn register(webview_window: &TauriWebviewWindow, args: Py<PyDict>) {
+        // NOTE: if `false`, it's already managed (maybe by other threads at the same time)
+        let _ = webview_window.manage(Self::default());
+        let label = webview_window.label().to_owned();
+        let args = Arc::new(args);
+        let registered = Arc::downgrade(&args);
+
+        webview_window
+            .state::<Self>()
+            .0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(label.clone(), args);
+
+        let app_handle = webview_window.app_handle().clone();
+        webview_window.on_window_event(move |event| {
+            if !matches!(event, tauri::WindowEvent::Destroyed) {
+                return;
+            }
+            let mut entries = app_handle
+                .state::<Self>()
+                .inner()
+                .0
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            // the label may have been reused by a new webview window
+            if entries
+                .get(&label)
+                .is_some_and(|args| Arc::as_ptr(args) == registered.as_ptr())
+            {
+                entries.remove(&label);
+            }
+        });
+    }
+
+    fn get(py: Python<'_>, webview_window: &TauriWebviewWindow) -> Option<Py<PyDict>> {
+        let this = webview_window.try_state::<Self>()?;
+        let entries = this.0.lock().unwrap_or_else(PoisonError::into_inner);
+        entries
+            .get(webview_window.label())
+            .map(|args| args.clone_ref(py))
+    }
+}
+
 /// See also: [tauri::webview::WebviewWindowBuilder]
 #[pyclass(frozen)]
 #[non_exhaustive]
@@ -1392,6 +1399,7 @@ impl WebviewWindowBuilder {
         let args = WebviewWindowBuilderArgs::from_kwargs(kwargs)?;
         manager_method_impl!(py, &manager, move |py, manager| {
             let mut builder = webview::WebviewWindowBuilder::new(manager, label, url);
+            let kwargs = WebviewWindowBuilderArgs::to_kwargs(args.as_ref(), py)?.unbind();
             if let Some(args) = args {
                 builder = args.apply_to_builder(py, builder)?;
             }
@@ -1401,6 +1409,7 @@ impl WebviewWindowBuilder {
                 py.allow_threads_unsend(builder, |builder| builder.build())
             }
             .map_err(TauriError::from)?;
+            BuilderArgsStore::register(&webview_window, kwargs);
 
             PyResult::Ok(WebviewWindow::new(webview_window))
         })?
@@ -1419,6 +1428,7 @@ impl WebviewWindowBuilder {
         manager_method_impl!(py, &manager, move |py, manager| {
             let mut builder = webview::WebviewWindowBuilder::from_config(manager, &config)
                 .map_err(TauriError::from)?;
+            let kwargs = WebviewWindowBuilderArgs::to_kwargs(args.as_ref(), py)?.unbind();
             if let Some(args) = args {
                 builder = args.apply_to_builder(py, builder)?;
             }
@@ -1428,6 +1438,7 @@ impl WebviewWindowBuilder {
                 py.allow_threads_unsend(builder, |builder| builder.build())
             }
             .map_err(TauriError::from)?;
+            BuilderArgsStore::register(&webview_window, kwargs);
 
             PyResult::Ok(WebviewWindow::new(webview_window))
         })?
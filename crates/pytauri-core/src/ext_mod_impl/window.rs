@@ -3,7 +3,7 @@ use pyo3::{
     types::{PyDict, PyFloat, PyString},
 };
 use pyo3_utils::{
-    from_py_dict::{derive_from_py_dict, FromPyDict as _, NotRequired},
+    from_py_dict::{FromPyDict, IntoPyDict, NotRequired},
     py_wrapper::{PyWrapper, PyWrapperT0},
 };
 use tauri::window;
@@ -11,7 +11,7 @@ use tauri::window;
 use crate::{
     ext_mod::{webview::Color, PhysicalPositionI32, PhysicalRect, PhysicalSizeU32},
     tauri_runtime::Runtime,
-    utils::non_exhaustive_panic,
+    utils::{into_pyobject_for_copy_ref, non_exhaustive_panic},
 };
 
 type TauriWindow = window::Window<Runtime>;
@@ -121,6 +121,8 @@ effect_impl!(
     Acrylic
 );
 
+into_pyobject_for_copy_ref!(Effect);
+
 macro_rules! effect_state_impl {
     ($ident:ident => : $($variant:ident),*) => {
         /// See also: [tauri::window::EffectState]
@@ -151,25 +153,21 @@ macro_rules! effect_state_impl {
 
 effect_state_impl!(EffectState => : FollowsWindowActiveState, Active, Inactive);
 
+into_pyobject_for_copy_ref!(EffectState);
+
 /// See also: [tauri::window::EffectsBuilder]
+#[derive(FromPyDict, IntoPyDict)]
 pub struct Effects {
+    #[pyo3(default)]
     effects: NotRequired<Vec<Effect>>,
+    #[pyo3(default)]
     state: NotRequired<EffectState>,
+    #[pyo3(default)]
     radius: NotRequired<f64>,
+    #[pyo3(default)]
     color: NotRequired<Color>,
 }
 
-derive_from_py_dict!(Effects {
-    #[pyo3(default)]
-    effects,
-    #[pyo3(default)]
-    state,
-    #[pyo3(default)]
-    radius,
-    #[pyo3(default)]
-    color,
-});
-
 impl<'py> FromPyObject<'py> for Effects {
     fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
         let dict = ob.downcast::<PyDict>()?;
@@ -177,6 +175,16 @@ impl<'py> FromPyObject<'py> for Effects {
     }
 }
 
+impl<'py> IntoPyObject<'py> for &Effects {
+    type Target = PyDict;
+    type Output = Bound<'py, Self::Target>;
+    type Error = PyErr;
+
+    fn into_pyobject(self, py: Python<'py>) -> Result<Self::Output, Self::Error> {
+        self.to_py_dict(py)
+    }
+}
+
 impl Effects {
     // NOTE: We do not want to use [tauri::utils::config::WindowEffectsConfig],
     // because it comes from `tauri_utils` (which may be unstable).
@@ -249,18 +257,14 @@ progress_bar_status_impl!(
 );
 
 /// See also: [tauri::window::ProgressBarState]
+#[derive(FromPyDict)]
 pub struct ProgressBarState {
+    #[pyo3(default)]
     status: NotRequired<ProgressBarStatus>,
+    #[pyo3(default)]
     progress: NotRequired<u64>,
 }
 
-derive_from_py_dict!(ProgressBarState {
-    #[pyo3(default)]
-    status,
-    #[pyo3(default)]
-    progress,
-});
-
 impl<'py> FromPyObject<'py> for ProgressBarState {
     fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
         let dict = ob.downcast::<PyDict>()?;
@@ -316,3 +320,5 @@ title_bar_style_impl!(
     Transparent,
     Overlay
 );
+
+into_pyobject_for_copy_ref!(TitleBarStyle);
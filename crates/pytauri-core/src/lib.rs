@@ -76,7 +76,17 @@ pub mod ext_mod {
         use super::*;
 
         #[pymodule_export]
-        pub use ext_mod_impl::ipc::{Channel, Invoke, InvokeResolver, JavaScriptChannelId};
+        pub use ext_mod_impl::ipc::{
+            Channel, Headers, HeadersIter, Invoke, InvokeResolver, JavaScriptChannelId,
+        };
+
+        pub use ext_mod_impl::ipc::{
+            call_channel_on_close, close_channels, exception_to_json, ChannelCloseEvent,
+            InvokeOutcome,
+        };
+
+        #[cfg(feature = "__private")]
+        pub use ext_mod_impl::ipc::{InvokeCancelHandle, InvokeResponder};
     }
 
     /// See also: [tauri::webview]
@@ -159,6 +169,19 @@ pub mod ext_mod {
         pub use ext_mod_impl::path::PathResolver;
     }
 
+    /// Bridges the Rust `log` records and the Python `logging` module, see [pyo3_utils::log].
+    ///
+    /// Use [pyo3_utils::log::PyLogger] to forward the Rust records (including the ones
+    /// emitted by pytauri) to Python, and `pytauri.log.RustHandler` to forward the Python
+    /// records to Rust.
+    #[pymodule]
+    pub mod log {
+        use super::*;
+
+        #[pymodule_export]
+        pub use ext_mod_impl::log::log_record;
+    }
+
     /// See also: [tauri::plugin]
     #[pymodule]
     pub mod plugin {
@@ -11,7 +11,7 @@ use pyo3::{
     pybacked::PyBackedStr,
     types::{PyDict, PyString},
 };
-use pyo3_utils::from_py_dict::{derive_from_py_dict, FromPyDict as _, NotRequired};
+use pyo3_utils::from_py_dict::{FromPyDict, NotRequired};
 use tauri::Manager as _;
 use tauri_plugin_dialog::{self as plugin, DialogExt as _};
 
@@ -134,24 +134,18 @@ message_dialog_kind_impl!(
 
 /// See also: [tauri_plugin_dialog::MessageDialogBuilder]
 #[non_exhaustive]
+#[derive(FromPyDict)]
 pub struct MessageDialogBuilderArgs {
+    #[pyo3(default)]
     title: NotRequired<String>,
+    #[pyo3(default)]
     parent: NotRequired<HasWindowHandleAndHasDisplayHandle>,
+    #[pyo3(default)]
     buttons: NotRequired<Py<MessageDialogButtons>>,
+    #[pyo3(default)]
     kind: NotRequired<Py<MessageDialogKind>>,
 }
 
-derive_from_py_dict!(MessageDialogBuilderArgs {
-    #[pyo3(default)]
-    title,
-    #[pyo3(default)]
-    parent,
-    #[pyo3(default)]
-    buttons,
-    #[pyo3(default)]
-    kind,
-});
-
 impl MessageDialogBuilderArgs {
     fn from_kwargs(kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<Option<Self>> {
         kwargs
@@ -304,32 +298,24 @@ impl<'py> IntoPyObject<'py> for FilePath {
 
 /// See also: [tauri_plugin_dialog::FileDialogBuilder]
 #[non_exhaustive]
+#[derive(FromPyDict)]
 pub struct FileDialogBuilderArgs {
     // TODO, PERF: avoid `Vec`, use `PyIterable` or `smallvec` instead.
+    #[pyo3(default)]
     add_filter: NotRequired<(String, Vec<PyBackedStr>)>,
     // PERF: avoid `PathBuf`, prefer `&Path` instead.
+    #[pyo3(default)]
     set_directory: NotRequired<PathBuf>,
+    #[pyo3(default)]
     set_file_name: NotRequired<String>,
+    #[pyo3(default)]
     set_parent: NotRequired<HasWindowHandleAndHasDisplayHandle>,
+    #[pyo3(default)]
     set_title: NotRequired<String>,
+    #[pyo3(default)]
     set_can_create_directories: NotRequired<bool>,
 }
 
-derive_from_py_dict!(FileDialogBuilderArgs {
-    #[pyo3(default)]
-    add_filter,
-    #[pyo3(default)]
-    set_directory,
-    #[pyo3(default)]
-    set_file_name,
-    #[pyo3(default)]
-    set_parent,
-    #[pyo3(default)]
-    set_title,
-    #[pyo3(default)]
-    set_can_create_directories,
-});
-
 impl FileDialogBuilderArgs {
     fn from_kwargs(kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<Option<Self>> {
         kwargs.map(FileDialogBuilderArgs::from_py_dict).transpose()
@@ -5,7 +5,7 @@ use std::{
 
 use pyo3::{prelude::*, types::PyDict};
 use pyo3_utils::{
-    from_py_dict::{derive_from_py_dict, FromPyDict as _, NotRequired},
+    from_py_dict::{FromPyDict, NotRequired},
     py_wrapper::{PyWrapper, PyWrapperT2},
 };
 use tauri_plugin_notification::{self as plugin, NotificationExt as _};
@@ -48,66 +48,47 @@ pub fn init() -> Plugin {
 
 /// See also: [tauri_plugin_notification::NotificationBuilder]
 #[non_exhaustive]
+#[derive(FromPyDict)]
 pub struct NotificationBuilderArgs {
+    #[pyo3(default)]
     id: NotRequired<i32>,
+    #[pyo3(default)]
     channel_id: NotRequired<String>,
+    #[pyo3(default)]
     title: NotRequired<String>,
+    #[pyo3(default)]
     body: NotRequired<String>,
     /* TODO: schedule */
+    #[pyo3(default)]
     large_body: NotRequired<String>,
+    #[pyo3(default)]
     summary: NotRequired<String>,
+    #[pyo3(default)]
     action_type_id: NotRequired<String>,
+    #[pyo3(default)]
     group: NotRequired<String>,
+    #[pyo3(default)]
     group_summary: bool,
+    #[pyo3(default)]
     sound: NotRequired<String>,
+    #[pyo3(default)]
     inbox_line: NotRequired<String>,
+    #[pyo3(default)]
     icon: NotRequired<String>,
+    #[pyo3(default)]
     large_icon: NotRequired<String>,
+    #[pyo3(default)]
     icon_color: NotRequired<String>,
     /* TODO: attachment */
     /* TODO: extra */
+    #[pyo3(default)]
     ongoing: bool,
+    #[pyo3(default)]
     auto_cancel: bool,
+    #[pyo3(default)]
     silent: bool,
 }
 
-derive_from_py_dict!(NotificationBuilderArgs {
-    #[pyo3(default)]
-    id,
-    #[pyo3(default)]
-    channel_id,
-    #[pyo3(default)]
-    title,
-    #[pyo3(default)]
-    body,
-    #[pyo3(default)]
-    large_body,
-    #[pyo3(default)]
-    summary,
-    #[pyo3(default)]
-    action_type_id,
-    #[pyo3(default)]
-    group,
-    #[pyo3(default)]
-    group_summary,
-    #[pyo3(default)]
-    sound,
-    #[pyo3(default)]
-    inbox_line,
-    #[pyo3(default)]
-    icon,
-    #[pyo3(default)]
-    large_icon,
-    #[pyo3(default)]
-    icon_color,
-    #[pyo3(default)]
-    ongoing,
-    #[pyo3(default)]
-    auto_cancel,
-    #[pyo3(default)]
-    silent,
-});
-
 impl NotificationBuilderArgs {
     // TODO: Maybe we can upstream this to pyo3,
     // so that we can directly use it as the type signature for `**kwargs`
@@ -4,7 +4,7 @@ use std::{
 };
 
 use pyo3::{exceptions::PyRuntimeError, prelude::*, types::PyDict};
-use pyo3_utils::from_py_dict::{derive_from_py_dict, FromPyDict as _};
+use pyo3_utils::from_py_dict::FromPyDict;
 use tauri_plugin_updater::{self as plugin};
 
 use crate::{ext_mod::plugin::Plugin, utils::TauriError};
@@ -69,10 +69,9 @@ impl From<plugin::Error> for PluginError {
 
 /// See also: [tauri_plugin_updater::Builder]
 #[non_exhaustive]
+#[derive(FromPyDict)]
 pub struct BuilderArgs {}
 
-derive_from_py_dict!(BuilderArgs {});
-
 impl BuilderArgs {
     fn from_kwargs(kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<Option<Self>> {
         kwargs.map(Self::from_py_dict).transpose()
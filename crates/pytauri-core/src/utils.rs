@@ -73,6 +73,15 @@ pub(crate) trait PyResultExt {
     ) -> Self::Output
     where
         M: Any + Send + 'static;
+
+    /// Logs the error with `msg` and writes it to `sys.unraisablehook`, instead of panicking.
+    fn ok_or_log_unraisable<M>(
+        self,
+        py: Python<'_>,
+        msg: impl FnOnce() -> M,
+    ) -> Option<Self::Output>
+    where
+        M: Display;
 }
 
 impl<T> PyResultExt for PyResult<T> {
@@ -102,6 +111,24 @@ impl<T> PyResultExt for PyResult<T> {
             }
         }
     }
+
+    fn ok_or_log_unraisable<M>(
+        self,
+        py: Python<'_>,
+        msg: impl FnOnce() -> M,
+    ) -> Option<Self::Output>
+    where
+        M: Display,
+    {
+        match self {
+            Ok(v) => Some(v),
+            Err(err) => {
+                log::error!("{}: {err}", msg());
+                err.write_unraisable(py, None);
+                None
+            }
+        }
+    }
 }
 
 macro_rules! delegate_inner {
@@ -141,3 +168,23 @@ macro_rules! cfg_impl {
 }
 
 pub(crate) use cfg_impl;
+
+/// Implements [IntoPyObject] for `&$ty` by copying, `$ty` must be a `Copy` pyclass.
+///
+/// It's required by [NotRequired::into_py_with](pyo3_utils::from_py_dict::NotRequired::into_py_with)
+/// and [IntoPyDict](pyo3_utils::from_py_dict::IntoPyDict).
+macro_rules! into_pyobject_for_copy_ref {
+    ($ty:ty) => {
+        impl<'py> ::pyo3::IntoPyObject<'py> for &$ty {
+            type Error = <$ty as ::pyo3::IntoPyObject<'py>>::Error;
+            type Output = <$ty as ::pyo3::IntoPyObject<'py>>::Output;
+            type Target = <$ty as ::pyo3::IntoPyObject<'py>>::Target;
+
+            fn into_pyobject(self, py: ::pyo3::Python<'py>) -> Result<Self::Output, Self::Error> {
+                <$ty as ::pyo3::IntoPyObject<'py>>::into_pyobject(*self, py)
+            }
+        }
+    };
+}
+
+pub(crate) use into_pyobject_for_copy_ref;
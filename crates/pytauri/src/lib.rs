@@ -20,7 +20,7 @@ use pyo3::{
     wrap_pymodule,
 };
 use pyo3_utils::{
-    from_py_dict::{derive_from_py_dict, FromPyDict as _, NotRequired},
+    from_py_dict::{FromPyDict, NotRequired},
     py_wrapper::{PyWrapper, PyWrapperT2},
 };
 use pytauri_core::{ext_mod::PyAppHandleExt as _, tauri_runtime::Runtime, utils::TauriError};
@@ -33,25 +33,21 @@ type TauriContext = tauri::Context<Runtime>;
 
 /// See also: [tauri::Builder]. And please refer to the Python-side documentation.
 #[non_exhaustive]
+#[derive(FromPyDict)]
 pub struct BuilderArgs {
+    // We require `invoke_handler` as a required parameter,
+    // see: <https://github.com/pytauri/pytauri/pull/133>.
+    //
     /// see [`tauri_plugin_pytauri::init`] for `invoke_handler`.
     invoke_handler: Option<PyObject>,
     /// see [tauri::Builder::setup] and python side type hint.
+    #[pyo3(default)]
     setup: NotRequired<PyObject>,
     /// see [tauri::Builder::plugin]
+    #[pyo3(default)]
     plugins: NotRequired<Vec<Py<ext_mod::plugin::Plugin>>>,
 }
 
-derive_from_py_dict!(BuilderArgs {
-    // We require `invoke_handler` as a required parameter,
-    // see: <https://github.com/pytauri/pytauri/pull/133>.
-    invoke_handler,
-    #[pyo3(default)]
-    setup,
-    #[pyo3(default)]
-    plugins,
-});
-
 impl BuilderArgs {
     fn from_kwargs(kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<Self> {
         match kwargs {
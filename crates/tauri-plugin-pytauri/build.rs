@@ -1,8 +1,21 @@
 use std::env;
 use std::path::PathBuf;
 
+use serde::Deserialize;
+
+const PLUGIN_NAME: &str = "pytauri";
 const COMMANDS: &[&str] = &["pyfunc"];
 
+/// The `plugins.pytauri` config of `tauri.conf.json`, see `PluginConfig` in `src/lib.rs`.
+///
+/// The tauri cli passes it to us through the `TAURI_PYTAURI_PLUGIN_CONFIG` env var,
+/// see [tauri_plugin::plugin_config].
+#[derive(Deserialize, Default)]
+struct PluginConfig {
+    #[serde(default)]
+    commands: Vec<String>,
+}
+
 fn main() {
     // for `#[cfg(not(Py_GIL_DISABLED))]`,
     // see <https://pyo3.rs/v0.23.2/building-and-distribution/multiple-python-versions.html#using-pyo3-build-config>
@@ -19,7 +32,19 @@ fn main() {
         global_api_script_path.set_extension("prod.js");
     }
 
-    tauri_plugin::Builder::new(COMMANDS)
+    // NOTE: `plugin_config` only prints this if the env var is set,
+    // but we also need to rerun once it's set.
+    println!("cargo:rerun-if-env-changed=TAURI_PYTAURI_PLUGIN_CONFIG");
+    let config = tauri_plugin::plugin_config::<PluginConfig>(PLUGIN_NAME).unwrap_or_default();
+    // generate the `allow-<command>` and `deny-<command>` permissions for the native commands,
+    // it's ok to leak the memory in the build script.
+    let commands = COMMANDS
+        .iter()
+        .copied()
+        .chain(config.commands.into_iter().map(|command| &*command.leak()))
+        .collect::<Vec<_>>();
+
+    tauri_plugin::Builder::new(&commands)
         .global_api_script_path(global_api_script_path)
         .build();
 }
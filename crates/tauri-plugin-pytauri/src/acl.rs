@@ -0,0 +1,50 @@
+use serde::Deserialize;
+use tauri::ipc::{CommandArg as _, CommandItem, CommandScope, InvokeError, ScopeObjectMatch};
+
+use crate::commands::IpcInvoke;
+use crate::PLUGIN_NAME;
+
+/// The command scope entry that allows or denies Python commands,
+/// see the [crate-level documentation](crate) for the usage.
+#[derive(Debug, Deserialize)]
+#[non_exhaustive]
+pub struct PyCommandScope {
+    /// The name of the Python command.
+    ///
+    /// A trailing `*` matches any suffix, e.g., `"admin_*"`, or `"*"` for all commands.
+    pub command: String,
+}
+
+impl ScopeObjectMatch for PyCommandScope {
+    type Input = str;
+
+    fn matches(&self, input: &str) -> bool {
+        match self.command.strip_suffix('*') {
+            Some(prefix) => input.starts_with(prefix),
+            None => self.command == input,
+        }
+    }
+}
+
+/// Checks whether the Python `command` is allowed by the command scope of the invoking webview.
+///
+/// - If there is no allow scope, all commands that are not denied are allowed,
+///   so the capabilities without scopes (e.g., `pytauri:default`) allow all commands.
+/// - If the command is not allowed, returns the error to reject the ipc with.
+pub(crate) fn check_command_scope(invoke: &IpcInvoke, command: &str) -> Result<(), InvokeError> {
+    let item = CommandItem {
+        plugin: Some(PLUGIN_NAME),
+        name: "__whatever__pyfunc",
+        key: "__whatever__commandScope",
+        message: &invoke.message,
+        acl: &invoke.acl,
+    };
+    let scope = CommandScope::<PyCommandScope>::from_command(item)?;
+    if scope.matches(command) {
+        Ok(())
+    } else {
+        Err(InvokeError::from(format!(
+            "Python command `{command}` not allowed by ACL"
+        )))
+    }
+}
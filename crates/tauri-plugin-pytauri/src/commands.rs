@@ -1,51 +1,214 @@
-use pyo3::exceptions::PyRuntimeError;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::pin::Pin;
+
 use pyo3::prelude::*;
-use pytauri_core::ext_mod::ipc::Invoke;
+use pytauri_core::ext_mod::ipc::{Invoke, InvokeOutcome};
 use pytauri_core::tauri_runtime::Runtime as PyTauriRuntime;
-use tauri::ipc;
+use tauri::{ipc, Manager as _};
+
+pub(crate) type IpcInvoke = ipc::Invoke<PyTauriRuntime>;
+pub(crate) type IpcInvokeMessage = ipc::InvokeMessage<PyTauriRuntime>;
+
+const PYFUNC_COMMAND: &str = "pyfunc";
+
+use crate::acl::check_command_scope;
+use crate::deadline::InvokeDeadlines;
+use crate::gil_runtime::GilScheduler;
+use crate::invoke_error::PyInvokeErrorHandler;
+use crate::metrics::{InvokeTimer, IpcMetrics};
+use crate::origin::OriginPolicyState;
+use crate::record::{IpcRecorder, PendingRecord};
+use crate::PyInvokeHandler;
 
-type IpcInvoke = ipc::Invoke<PyTauriRuntime>;
+/// How the command name of the ipc is obtained.
+#[derive(Clone, Copy)]
+enum CommandRoute {
+    /// `plugin:pytauri|pyfunc` with the `pyfunc` header, see [Invoke::new].
+    PyfuncHeader,
+    /// `plugin:pytauri|<command>`, see [Invoke::from_command].
+    Native,
+}
 
-use crate::gil_runtime::task_with_gil;
-use crate::PyInvokeHandlerExt as _;
+fn pyfunc(
+    invoke: IpcInvoke,
+    route: CommandRoute,
+    timer: InvokeTimer,
+    record: Option<PendingRecord>,
+) {
+    let webview = invoke.message.webview();
+    // NOTE: clone it to release the borrow of `webview`, it's cheap
+    let gil_scheduler = webview.state::<GilScheduler>().inner().clone();
+    gil_scheduler.task_with_gil(move |py| {
+        #[cfg(feature = "tracing")]
+        let span = timer.span().clone();
+        #[cfg(feature = "tracing")]
+        let _entered = span.enter();
+        let on_complete = timer.gil_acquired();
+        let on_complete = move |outcome, response: &_| {
+            on_complete(outcome, response);
+            if let Some(record) = record {
+                record.finish(response);
+            }
+        };
 
-fn pyfunc(invoke: IpcInvoke) {
-    task_with_gil(move |py| {
-        let py_invoke_handler = invoke
-            .message
-            .webview_ref()
-            .try_py_invoke_handler()
-            // it's ok to `unwrap` here, because the plugin is already initialized
-            .unwrap()
+        let py_invoke_handler = webview
+            .state::<PyInvokeHandler>()
+            .resolve_webview(&webview)
             .bind(py)
             .clone();
 
-        let invoke = match Invoke::new(py, invoke) {
-            Some(invoke) => invoke,
-            None => return, // the ipc has already been handled and rejected
+        let invoke = match route {
+            CommandRoute::PyfuncHeader => match Invoke::new(py, invoke) {
+                Some(invoke) => invoke,
+                None => return, // the ipc has already been handled and rejected
+            },
+            CommandRoute::Native => Invoke::from_command(py, invoke),
+        };
+
+        invoke.set_on_complete(on_complete);
+
+        let deadlines = webview.state::<InvokeDeadlines>();
+        if let Some(timeout) = invoke
+            .command(py)
+            .to_cow()
+            .ok()
+            .and_then(|command| deadlines.timeout(&command))
+        {
+            if let Some(cancel_handle) = invoke.cancel_handle() {
+                InvokeDeadlines::spawn_timer(timeout, cancel_handle);
+            }
+        }
+
+        let invoke = match Bound::new(py, invoke) {
+            Ok(invoke) => invoke,
+            Err(e) => {
+                // the `invoke` has been dropped (and rejected), so there is nothing we can do
+                e.write_unraisable(py, Some(&py_invoke_handler));
+                return;
+            }
         };
 
         // NOTE: We require that the implementation of `py_invoke_handler`
         // does not block for a long time, so this call will not block
         // the tokio runtime.
-        if let Err(e) = py_invoke_handler.call1((invoke,)) {
-            let new_err = PyRuntimeError::new_err("`py_invoke_handler` raised an exception");
-            new_err.set_cause(py, Some(e));
-            // TODO: use [pytauri_core::utils::PyResultExt::unwrap_unraisable_py_result]
-            new_err.write_unraisable(py, Some(&py_invoke_handler));
-            // TODO: use `log` instead of `panic!`,
-            // it's because the joinhandle will never be awaited
-            panic!("`py_invoke_handler` shouldn't raise exception");
+        if let Err(e) = py_invoke_handler.call1((&invoke,)) {
+            webview
+                .state::<PyInvokeErrorHandler>()
+                .handle(py, &py_invoke_handler, invoke.get(), e);
         }
     });
 }
 
+/// The command names that can be called via `plugin:pytauri|<command>`,
+/// see [crate::Builder::commands].
+pub(crate) struct NativeCommands(pub(crate) HashSet<String>);
+
+impl NativeCommands {
+    fn contains(&self, command: &str) -> bool {
+        self.0.contains(command)
+    }
+}
+
+pub(crate) type RustCommandFuture = Pin<Box<dyn Future<Output = ipc::InvokeResponse> + Send>>;
+pub(crate) type RustCommandHandler =
+    dyn Fn(IpcInvokeMessage) -> RustCommandFuture + Send + Sync + 'static;
+
+/// The commands handled in Rust, see [crate::Builder::rust_command].
+pub(crate) struct RustCommands(pub(crate) HashMap<String, Box<RustCommandHandler>>);
+
 pub(crate) fn invoke_handler(invoke: IpcInvoke) -> bool {
-    match invoke.message.command() {
-        "pyfunc" => {
-            pyfunc(invoke);
-            true
+    let route = match invoke.message.command() {
+        // for backwards compatibility, and for `pyInvoke`
+        PYFUNC_COMMAND => CommandRoute::PyfuncHeader,
+        command => {
+            let webview = invoke.message.webview_ref();
+            let accepted = webview.state::<NativeCommands>().contains(command)
+                || webview.state::<RustCommands>().0.contains_key(command);
+            if !accepted {
+                // tauri will reject it with "command not found"
+                return false;
+            }
+            CommandRoute::Native
+        }
+    };
+
+    let command = match route {
+        CommandRoute::PyfuncHeader => Invoke::header_command(&invoke.message),
+        CommandRoute::Native => Ok(invoke.message.command()),
+    };
+    let command = match command {
+        Ok(command) => command.to_owned(),
+        Err(e) => {
+            invoke.resolver.reject(e);
+            return true;
         }
-        _ => false,
+    };
+
+    // NOTE: check the origin and the ACL before acquiring the GIL
+    let webview = invoke.message.webview_ref();
+    let metrics = webview.state::<IpcMetrics>();
+    if let Err(url) = webview.state::<OriginPolicyState>().0.check(webview) {
+        log::warn!(
+            "Rejected the ipc `{command}` from `{url}` (webview `{}`), which is not allowed by the origin policy of `{}`",
+            webview.label(),
+            env!("CARGO_PKG_NAME"),
+        );
+        metrics.denied(&command);
+        invoke.resolver.reject(format!(
+            "origin `{url}` is not allowed to call Python commands"
+        ));
+        return true;
+    }
+    if let Err(e) = check_command_scope(&invoke, &command) {
+        metrics.denied(&command);
+        invoke.resolver.invoke_error(e);
+        return true;
+    }
+
+    let record = webview.state::<IpcRecorder>().start(
+        webview,
+        invoke.message.command(),
+        &command,
+        invoke.message.headers(),
+        invoke.message.payload(),
+    );
+    // NOTE: use an owned webview (it's cheap), so the handler doesn't borrow `invoke`
+    let owned_webview = invoke.message.webview();
+    if let Some(handler) = owned_webview.state::<RustCommands>().0.get(&command) {
+        let on_complete = metrics.start(command).without_gil();
+        rust_command(invoke, handler, on_complete, record);
+        return true;
     }
+
+    let timer = metrics.start(command);
+    pyfunc(invoke, route, timer, record);
+    true
+}
+
+fn rust_command(
+    invoke: IpcInvoke,
+    handler: &RustCommandHandler,
+    on_complete: impl FnOnce(InvokeOutcome, &ipc::InvokeResponse) + Send + 'static,
+    record: Option<PendingRecord>,
+) {
+    let IpcInvoke {
+        message, resolver, ..
+    } = invoke;
+    let response = handler(message);
+    resolver.respond_async_serialized(async move {
+        let response = response.await;
+        let outcome = match response {
+            ipc::InvokeResponse::Ok(_) => InvokeOutcome::Resolved,
+            ipc::InvokeResponse::Err(_) => InvokeOutcome::Rejected,
+        };
+        on_complete(outcome, &response);
+        if let Some(record) = record {
+            record.finish(&response);
+        }
+        match response {
+            ipc::InvokeResponse::Ok(body) => Ok(body),
+            ipc::InvokeResponse::Err(error) => Err(error),
+        }
+    });
 }
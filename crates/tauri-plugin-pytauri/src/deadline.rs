@@ -0,0 +1,29 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use pytauri_core::ext_mod::ipc::InvokeCancelHandle;
+
+/// The deadlines of the ipc requests, see [crate::Builder::invoke_timeout].
+#[derive(Default)]
+pub(crate) struct InvokeDeadlines {
+    pub(crate) default: Option<Duration>,
+    pub(crate) commands: HashMap<String, Duration>,
+}
+
+impl InvokeDeadlines {
+    pub(crate) fn timeout(&self, command: &str) -> Option<Duration> {
+        self.commands.get(command).copied().or(self.default)
+    }
+
+    /// Cancels the ipc request after `timeout`, if it is still pending.
+    pub(crate) fn spawn_timer(timeout: Duration, cancel_handle: InvokeCancelHandle) {
+        // NOTE: don't use the GIL runtime, it may be blocked by the GIL,
+        // which is exactly the case that the deadline should handle.
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(timeout).await;
+            cancel_handle.cancel(&format!(
+                "python handler did not respond within {timeout:?}"
+            ));
+        });
+    }
+}
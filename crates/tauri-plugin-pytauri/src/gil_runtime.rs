@@ -1,9 +1,10 @@
+use std::num::NonZeroUsize;
 use std::sync::LazyLock;
+use std::time::Duration;
 
 use pyo3::prelude::*;
 
 use tokio::runtime as rt;
-use tokio::task::JoinHandle;
 
 /// This runtime is specifically for [std::future::Future] that requires acquiring the GIL
 pub(crate) static GIL_RUNTIME: LazyLock<rt::Runtime> = LazyLock::new(|| {
@@ -25,13 +26,65 @@ pub(crate) static GIL_RUNTIME: LazyLock<rt::Runtime> = LazyLock::new(|| {
     runtime
 });
 
+/// A task that will be executed while holding the GIL.
+#[cfg(not(Py_GIL_DISABLED))]
+pub(crate) type GilTask = Box<dyn for<'py> FnOnce(Python<'py>) + Send + 'static>;
+
+/// The configuration of how IPC invokes are scheduled onto the GIL.
+///
+/// For non-Free-Threaded CPython (`#[cfg(not(Py_GIL_DISABLED))]`),
+/// the invokes are put into a queue, and a single GIL acquisition drains
+/// a bounded batch of them, so that the GIL handoff cost is amortized.
+///
+/// For Free-Threaded CPython (`#[cfg(Py_GIL_DISABLED)]`), this configuration is ignored,
+/// every invoke is spawned as a separate task on the multi-worker runtime.
+#[derive(Debug, Clone, Copy)]
+pub struct GilSchedulerConfig {
+    max_batch_size: NonZeroUsize,
+    max_batch_duration: Duration,
+}
+
+impl Default for GilSchedulerConfig {
+    fn default() -> Self {
+        Self {
+            // `64` is not zero, so it's ok to `unwrap`
+            max_batch_size: NonZeroUsize::new(64).unwrap(),
+            max_batch_duration: Duration::from_millis(5),
+        }
+    }
+}
+
+impl GilSchedulerConfig {
+    /// The maximum number of tasks executed within a single GIL acquisition.
+    ///
+    /// Default is `64`. Set it to `1` to acquire the GIL once per task.
+    pub fn max_batch_size(mut self, max_batch_size: NonZeroUsize) -> Self {
+        self.max_batch_size = max_batch_size;
+        self
+    }
+
+    /// The maximum time a batch may hold the GIL.
+    ///
+    /// Once exceeded, the remaining queued tasks are scheduled in the next batch,
+    /// so other Python threads get the chance to acquire the GIL.
+    /// The task that is running when the limit is reached will not be interrupted.
+    ///
+    /// Default is `5ms`.
+    pub fn max_batch_duration(mut self, max_batch_duration: Duration) -> Self {
+        self.max_batch_duration = max_batch_duration;
+        self
+    }
+}
+
+/// Schedules [GilTask]s on [GIL_RUNTIME].
+///
 /// **If built for `#[cfg(not(Py_GIL_DISABLED))]`, please NOTE**:
 ///
 /// Do not use [Python::allow_threads] to temporarily release the GIL in a task,
 /// because reacquiring the GIL will block the tokio runtime.
 /// If you really need to release the GIL, use **another** tokio runtime to spawn
 /// a new task that does not require the GIL within the GIL task, and immediately end the GIL task;
-/// when you need the GIL again, simply call [task_with_gil] to reacquire the GIL.
+/// when you need the GIL again, simply call [GilScheduler::task_with_gil] to reacquire the GIL.
 ///
 /// > - If you do not use **another** tokio runtime to spawn new tasks,
 /// >     then when the GIL task in [GIL_RUNTIME] is running,
@@ -39,12 +92,259 @@ pub(crate) static GIL_RUNTIME: LazyLock<rt::Runtime> = LazyLock::new(|| {
 ///
 /// In short, when `#[cfg(not(Py_GIL_DISABLED))]` is true,
 /// this runtime can only be used for tasks that hold the GIL for their entire duration.
-// TODO, PERF: Use a queue to store tasks, and schedule multiple tasks at once when acquiring the GIL
-pub(crate) fn task_with_gil<F, R>(f: F) -> JoinHandle<R>
-where
-    F: for<'py> FnOnce(Python<'py>) -> R + Send + 'static,
-    R: Send + 'static,
-{
-    let future = async move { Python::with_gil(f) };
-    GIL_RUNTIME.spawn(future)
+#[derive(Clone)]
+pub(crate) struct GilScheduler {
+    #[cfg(not(Py_GIL_DISABLED))]
+    sender: tokio::sync::mpsc::UnboundedSender<GilTask>,
+}
+
+impl GilScheduler {
+    /// Creates a new scheduler, and spawns its worker on [GIL_RUNTIME].
+    ///
+    /// The worker exits once the [GilScheduler] (and all its clones) is dropped.
+    #[cfg(not(Py_GIL_DISABLED))]
+    pub(crate) fn new(config: GilSchedulerConfig) -> Self {
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        GIL_RUNTIME.spawn(Self::worker(receiver, config));
+        Self { sender }
+    }
+
+    /// Creates a new scheduler, `config` is ignored for Free-Threaded CPython.
+    #[cfg(Py_GIL_DISABLED)]
+    pub(crate) fn new(config: GilSchedulerConfig) -> Self {
+        let _ = config;
+        Self {}
+    }
+
+    #[cfg(not(Py_GIL_DISABLED))]
+    async fn worker(
+        mut receiver: tokio::sync::mpsc::UnboundedReceiver<GilTask>,
+        config: GilSchedulerConfig,
+    ) {
+        // NOTE: only wait for the first task without the GIL,
+        // so we will not acquire the GIL if there is nothing to do.
+        while let Some(first_task) = receiver.recv().await {
+            Python::with_gil(|py| Self::run_batch(py, first_task, &mut receiver, config));
+        }
+    }
+
+    /// Runs `first_task` and then the queued tasks within the limits of `config`,
+    /// returns the number of tasks that have been run.
+    #[cfg(not(Py_GIL_DISABLED))]
+    fn run_batch(
+        py: Python<'_>,
+        first_task: GilTask,
+        receiver: &mut tokio::sync::mpsc::UnboundedReceiver<GilTask>,
+        config: GilSchedulerConfig,
+    ) -> usize {
+        use std::time::Instant;
+
+        use tokio::sync::mpsc::error::TryRecvError;
+
+        let GilSchedulerConfig {
+            max_batch_size,
+            max_batch_duration,
+        } = config;
+
+        let deadline = Instant::now() + max_batch_duration;
+        Self::run_task(py, first_task);
+
+        let mut count = 1;
+        while count < max_batch_size.get() {
+            if Instant::now() >= deadline {
+                break;
+            }
+            match receiver.try_recv() {
+                Ok(task) => Self::run_task(py, task),
+                Err(TryRecvError::Empty | TryRecvError::Disconnected) => break,
+            }
+            count += 1;
+        }
+        count
+    }
+
+    #[cfg(not(Py_GIL_DISABLED))]
+    #[inline]
+    fn run_task(py: Python<'_>, task: GilTask) {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        // Tasks are independent of each other, so a panicking task must not stop
+        // the whole queue (just like tokio catches the panic of a spawned task).
+        // The panic message has already been printed by the panic hook.
+        let _ = catch_unwind(AssertUnwindSafe(|| task(py)));
+    }
+
+    /// Schedules `f` to be executed while holding the GIL.
+    pub(crate) fn task_with_gil<F>(&self, f: F)
+    where
+        F: for<'py> FnOnce(Python<'py>) + Send + 'static,
+    {
+        #[cfg(not(Py_GIL_DISABLED))]
+        {
+            // The worker only exits after the `GilScheduler` is dropped,
+            // so the `Err` is unreachable in practice.
+            let _ = self.sender.send(Box::new(f));
+        }
+        #[cfg(Py_GIL_DISABLED)]
+        {
+            let future = async move { Python::with_gil(f) };
+            GIL_RUNTIME.spawn(future);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            mpsc, Arc,
+        },
+        thread,
+    };
+
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Returns a task that sends `id` to `sender` when it's run.
+    fn send_task(
+        sender: &mpsc::Sender<usize>,
+        id: usize,
+    ) -> impl for<'py> FnOnce(Python<'py>) + Send + 'static {
+        let sender = sender.clone();
+        move |_py| sender.send(id).unwrap()
+    }
+
+    #[cfg(not(Py_GIL_DISABLED))]
+    fn queue(
+        tasks: impl IntoIterator<Item = GilTask>,
+    ) -> tokio::sync::mpsc::UnboundedReceiver<GilTask> {
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        for task in tasks {
+            sender.send(task).unwrap();
+        }
+        receiver
+    }
+
+    #[cfg(not(Py_GIL_DISABLED))]
+    #[test]
+    fn test_max_batch_size() {
+        pyo3::prepare_freethreaded_python();
+
+        let count = Arc::new(AtomicUsize::new(0));
+        let task = || -> GilTask {
+            let count = count.clone();
+            Box::new(move |_py| {
+                count.fetch_add(1, Ordering::SeqCst);
+            })
+        };
+        let mut receiver = queue((0..9).map(|_| task()));
+        let config = GilSchedulerConfig::default()
+            .max_batch_size(NonZeroUsize::new(4).unwrap())
+            .max_batch_duration(TIMEOUT);
+
+        Python::with_gil(|py| {
+            assert_eq!(
+                GilScheduler::run_batch(py, task(), &mut receiver, config),
+                4
+            );
+            assert_eq!(count.load(Ordering::SeqCst), 4);
+            assert_eq!(receiver.len(), 6);
+
+            let first_task = receiver.try_recv().unwrap();
+            assert_eq!(
+                GilScheduler::run_batch(py, first_task, &mut receiver, config),
+                4
+            );
+            // the queue is drained before reaching `max_batch_size`
+            let first_task = receiver.try_recv().unwrap();
+            assert_eq!(
+                GilScheduler::run_batch(py, first_task, &mut receiver, config),
+                2
+            );
+            assert!(receiver.is_empty());
+            assert_eq!(count.load(Ordering::SeqCst), 10);
+        });
+    }
+
+    #[cfg(not(Py_GIL_DISABLED))]
+    #[test]
+    fn test_max_batch_duration() {
+        pyo3::prepare_freethreaded_python();
+
+        let slow_task = || -> GilTask { Box::new(|_py| thread::sleep(Duration::from_millis(20))) };
+        let mut receiver = queue([slow_task(), slow_task()]);
+        let config = GilSchedulerConfig::default().max_batch_duration(Duration::from_millis(5));
+
+        Python::with_gil(|py| {
+            // the running task is not interrupted, but the queued one is left to the next batch
+            assert_eq!(
+                GilScheduler::run_batch(py, slow_task(), &mut receiver, config),
+                1
+            );
+            assert_eq!(receiver.len(), 2);
+        });
+    }
+
+    #[cfg(not(Py_GIL_DISABLED))]
+    #[test]
+    fn test_panicking_task_in_batch() {
+        pyo3::prepare_freethreaded_python();
+
+        let (sender, results) = mpsc::channel();
+        let mut receiver = queue([
+            Box::new(|_py: Python<'_>| panic!("expected panic")) as GilTask,
+            Box::new(send_task(&sender, 1)),
+        ]);
+        let config = GilSchedulerConfig::default().max_batch_duration(TIMEOUT);
+
+        Python::with_gil(|py| {
+            let first_task = Box::new(send_task(&sender, 0));
+            assert_eq!(
+                GilScheduler::run_batch(py, first_task, &mut receiver, config),
+                3
+            );
+        });
+        assert_eq!(results.try_iter().collect::<Vec<_>>(), [0, 1]);
+    }
+
+    /// The worker keeps running the later tasks after a task panics.
+    #[test]
+    fn test_scheduler_survives_panic() {
+        pyo3::prepare_freethreaded_python();
+
+        let scheduler = GilScheduler::new(GilSchedulerConfig::default());
+        let (sender, results) = mpsc::channel();
+
+        scheduler.task_with_gil(send_task(&sender, 0));
+        scheduler.task_with_gil(|_py| panic!("expected panic"));
+        scheduler.task_with_gil(send_task(&sender, 1));
+        assert_eq!(results.recv_timeout(TIMEOUT), Ok(0));
+        assert_eq!(results.recv_timeout(TIMEOUT), Ok(1));
+
+        // the next batch
+        scheduler.task_with_gil(send_task(&sender, 2));
+        assert_eq!(results.recv_timeout(TIMEOUT), Ok(2));
+    }
+
+    /// Every task is run, no matter whether the GIL is disabled or not.
+    #[test]
+    fn test_runs_each_task() {
+        pyo3::prepare_freethreaded_python();
+
+        let scheduler = GilScheduler::new(GilSchedulerConfig::default());
+        let (sender, results) = mpsc::channel();
+
+        const N: usize = 100;
+        for id in 0..N {
+            scheduler.task_with_gil(send_task(&sender, id));
+        }
+        let mut ids = (0..N)
+            .map(|_| results.recv_timeout(TIMEOUT).unwrap())
+            .collect::<Vec<_>>();
+        // NOTE: the order is not guaranteed for Free-Threaded CPython
+        ids.sort_unstable();
+        assert_eq!(ids, (0..N).collect::<Vec<_>>());
+    }
 }
@@ -0,0 +1,55 @@
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use pytauri_core::ext_mod::ipc::{exception_to_json, Invoke};
+use pytauri_core::ext_mod::IS_DEV;
+
+/// Handles the exception raised by `py_invoke_handler`, see [crate::Builder::py_error_hook].
+pub(crate) struct PyInvokeErrorHandler {
+    hook: Option<PyObject>,
+    panic: bool,
+}
+
+impl PyInvokeErrorHandler {
+    pub(crate) fn new(hook: Option<PyObject>, panic: bool) -> Self {
+        Self { hook, panic }
+    }
+
+    /// - If `invoke` has not been consumed yet, reject it with [rejection_payload],
+    ///   so the frontend will not wait forever.
+    /// - Pass the exception to the error hook, or [PyErr::write_unraisable] if there is no hook.
+    /// - `panic!` if the user explicitly opts into it.
+    pub(crate) fn handle(
+        &self,
+        py: Python<'_>,
+        py_invoke_handler: &Bound<'_, PyAny>,
+        invoke: &Invoke,
+        err: PyErr,
+    ) {
+        if let Some(resolver) = invoke.try_take_resolver() {
+            resolver.reject(rejection_payload(py, &err));
+        }
+
+        let new_err = PyRuntimeError::new_err("`py_invoke_handler` raised an exception");
+        new_err.set_cause(py, Some(err));
+
+        match &self.hook {
+            Some(hook) => {
+                if let Err(hook_err) = hook.call1(py, (new_err.into_value(py),)) {
+                    hook_err.write_unraisable(py, Some(hook.bind(py)));
+                }
+            }
+            None => new_err.write_unraisable(py, Some(py_invoke_handler)),
+        }
+
+        if self.panic {
+            // NOTE: the panic will be caught by the GIL runtime, so it only aborts this ipc task.
+            panic!("`py_invoke_handler` shouldn't raise exception");
+        }
+    }
+}
+
+/// The structured payload used to reject the ipc when `py_invoke_handler` raises,
+/// see [exception_to_json]. The traceback is only included in dev builds.
+fn rejection_payload(py: Python<'_>, err: &PyErr) -> serde_json::Value {
+    exception_to_json(err.value(py), IS_DEV)
+}
@@ -1,38 +1,194 @@
 //! This crate is currently only used internally by pytauri to
 //! implement IPC communication between the frontend and Python.
+//!
+//! # Commands
+//!
+//! The frontend can call Python commands in two ways:
+//!
+//! - `invoke("plugin:pytauri|pyfunc", body, { headers: { pyfunc: "<command>" } })`,
+//!   which is what `pyInvoke` does.
+//! - `invoke("plugin:pytauri|<command>", body)`, which works with Tauri's per-command ACL,
+//!   devtools and the standard invoke tooling. Only the commands listed in the plugin config
+//!   (or [Builder::commands]) can be called this way.
+//!
+//! Both are dispatched to the same `py_invoke_handler` with the name in `Invoke.command`.
+//! Different windows can have different `py_invoke_handler`s, see [PyInvokeHandlerExt].
+//!
+//! Tauri checks the ACL of plugin commands before dispatching them, so list the native commands
+//! in the `plugins.pytauri` config of your `tauri.conf.json`:
+//!
+//! ```json
+//! {
+//!     "plugins": {
+//!         "pytauri": { "commands": ["greet"] }
+//!     }
+//! }
+//! ```
+//!
+//! The `build.rs` of this plugin generates the `pytauri:allow-<command>` and `pytauri:deny-<command>`
+//! permissions for them (the tauri cli passes the config with the `TAURI_PYTAURI_PLUGIN_CONFIG`
+//! env var, set it yourself if you build with `cargo` directly), so you can allow them
+//! in your capabilities as for any other plugin, e.g., `"pytauri:allow-greet"`.
+//!
+//! # ACL
+//!
+//! Besides the per-command ACL of `plugin:pytauri|<command>`, you can also allow or deny
+//! Python commands for each capability through the command scope (see [PyCommandScope]),
+//! which works for both ways of calling. For example, in `capabilities/preview.json`:
+//!
+//! ```json
+//! {
+//!     "identifier": "preview",
+//!     "windows": ["preview"],
+//!     "permissions": [
+//!         {
+//!             "identifier": "pytauri:allow-pyfunc",
+//!             "allow": [{ "command": "greet" }, { "command": "public_*" }],
+//!             "deny": [{ "command": "public_admin" }]
+//!         }
+//!     ]
+//! }
+//! ```
+//!
+//! Denied calls are rejected before acquiring the GIL. If there is no allow scope,
+//! all commands that are not denied are allowed, e.g., `pytauri:default`.
+//!
+//! Regardless of the ACL, only the local pages of the app can call Python commands by default,
+//! see [OriginPolicy].
+//!
+//! # Record and replay
+//!
+//! [Builder::record_ipc] records the ipc requests and their responses to a JSON lines file,
+//! which can be loaded with [IpcRecord::read_jsonl] and replayed with `replay`
+//! (requires the `test` feature) to catch regressions:
+//!
+//! ```ignore
+//! let records = IpcRecord::read_jsonl("session.jsonl")?;
+//! // `app` is built on `tauri::test::MockRuntime`, with the recorded windows created
+//! for mismatch in tauri_plugin_pytauri::replay(&app, &records) {
+//!     eprintln!("{mismatch}");
+//! }
+//! ```
 
+mod acl;
 mod commands;
+mod deadline;
 mod gil_runtime;
+mod invoke_error;
+mod metrics;
+mod origin;
+mod record;
 
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::fmt::Display;
+use std::future::Future;
 use std::ops::Deref;
+use std::path::PathBuf;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::Duration;
 
-use pyo3::exceptions::PyRuntimeError;
+use glob::Pattern;
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
 use pyo3::prelude::*;
+use pytauri_core::ext_mod::ipc::{call_channel_on_close, close_channels, ChannelCloseEvent};
 use pytauri_core::tauri_runtime::Runtime as PyTauriRuntime;
-use tauri::plugin::{Builder, TauriPlugin};
-use tauri::{Manager, Runtime};
+use serde::Deserialize;
+use tauri::plugin::{Builder as PluginBuilder, TauriPlugin};
+use tauri::webview::PageLoadEvent;
+use tauri::{AppHandle, Manager, RunEvent, Runtime, Webview, WindowEvent};
 
-use crate::commands::invoke_handler;
+use crate::commands::{
+    invoke_handler, NativeCommands, RustCommandFuture, RustCommandHandler, RustCommands,
+};
+use crate::deadline::InvokeDeadlines;
+use crate::gil_runtime::GilScheduler;
+use crate::invoke_error::PyInvokeErrorHandler;
+use crate::metrics::IpcMetrics;
+use crate::origin::OriginPolicyState;
+use crate::record::IpcRecorder;
+
+pub use crate::acl::PyCommandScope;
+
+pub use crate::gil_runtime::GilSchedulerConfig;
+pub use crate::metrics::{CommandMetrics, IpcMetricsExt};
+pub use crate::origin::{OriginPolicy, OriginPolicyError};
+#[cfg(feature = "test")]
+pub use crate::record::{replay, ReplayMismatch};
+pub use crate::record::{IpcRecord, RecordedBody, RecordedResponse};
 
 const PLUGIN_NAME: &str = "pytauri";
 
 type PyInvokeHandlerType = PyObject;
 
-struct PyInvokeHandler(PyInvokeHandlerType);
-
-impl Deref for PyInvokeHandler {
-    type Target = PyInvokeHandlerType;
+/// The `plugins.pytauri` config of `tauri.conf.json`.
+#[derive(Deserialize)]
+struct PluginConfig {
+    /// The native commands, see [Builder::commands].
+    ///
+    /// NOTE: keep it in sync with `build.rs`.
+    #[serde(default)]
+    commands: Vec<String>,
+}
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
+impl PluginConfig {
+    // NOTE: don't use the typed config of [PluginBuilder], which would change the type of [TauriPlugin].
+    fn from_app<R: Runtime>(app_handle: &AppHandle<R>) -> serde_json::Result<Self> {
+        match app_handle.config().plugins.0.get(PLUGIN_NAME) {
+            Some(config) => Self::deserialize(config),
+            None => Ok(Self {
+                commands: Vec::new(),
+            }),
+        }
     }
 }
 
+/// The `py_invoke_handler`s, see [PyInvokeHandlerExt].
+pub(crate) struct PyInvokeHandler {
+    default: Arc<PyInvokeHandlerType>,
+    labeled: RwLock<Vec<LabeledPyInvokeHandler>>,
+}
+
+/// See [PyInvokeHandlerExt::add_py_invoke_handler].
+struct LabeledPyInvokeHandler {
+    label: String,
+    pattern: Pattern,
+    handler: Arc<PyInvokeHandlerType>,
+}
+
 impl PyInvokeHandler {
     fn new(handler: PyInvokeHandlerType) -> Self {
-        Self(handler)
+        Self {
+            default: Arc::new(handler),
+            labeled: Default::default(),
+        }
+    }
+
+    fn labeled(&self) -> RwLockReadGuard<'_, Vec<LabeledPyInvokeHandler>> {
+        self.labeled.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn labeled_mut(&self) -> RwLockWriteGuard<'_, Vec<LabeledPyInvokeHandler>> {
+        self.labeled.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// See [PyInvokeHandlerExt::try_py_invoke_handler_for].
+    fn resolve(&self, labels: &[&str]) -> Arc<PyInvokeHandlerType> {
+        self.labeled()
+            .iter()
+            .find(|labeled| labels.iter().any(|label| labeled.pattern.matches(label)))
+            .map(|labeled| labeled.handler.clone())
+            .unwrap_or_else(|| self.default.clone())
+    }
+
+    /// Gets the handler of the ipc requests from `webview`,
+    /// see [PyInvokeHandlerExt::try_py_invoke_handler_for].
+    pub(crate) fn resolve_webview<R: Runtime>(
+        &self,
+        webview: &Webview<R>,
+    ) -> Arc<PyInvokeHandlerType> {
+        let window = webview.window_ref();
+        self.resolve(&[webview.label(), window.label()])
     }
 }
 
@@ -46,24 +202,291 @@ impl PyInvokeHandler {
 /// It will be stored in the tauri app state and used to handle ipc requests from the frontend.
 /// You can get its reference through [PyInvokeHandlerExt].
 ///
+/// This is equivalent to `Builder::new(py_invoke_handler).build()`,
+/// use [Builder] if you want to customize the plugin.
+///
 /// # NOTE:
 ///
 /// - `py_invoke_handler` will be called in a tokio runtime, so it must not block for a long time.
 ///     - `tokio runtime` means it is running on an external thread.
-/// - `py_invoke_handler` must not raise exceptions, otherwise it will result in logical undefined behavior.
+/// - `py_invoke_handler` should not raise exceptions. If it does, the [Invoke][pytauri_core::ext_mod::ipc::Invoke]
+///   will be rejected if it has not been consumed yet, see [Builder::py_error_hook] for more details.
 pub fn init(py_invoke_handler: PyInvokeHandlerType) -> TauriPlugin<PyTauriRuntime> {
-    Builder::<PyTauriRuntime>::new(PLUGIN_NAME)
-        .invoke_handler(invoke_handler)
-        .setup(|app_handle, _plugin_api| {
-            // if false, there has already state set for the app instance.
-            if !app_handle.manage(PyInvokeHandler::new(py_invoke_handler)) {
-                unreachable!(
-                    "`PyInvokeHandler` is private, so it is impossible for other crates to manage it"
-                )
-            }
-            Ok(())
-        })
-        .build()
+    Builder::new(py_invoke_handler).build()
+}
+
+/// The builder of the plugin, see [init] for the basic usage.
+pub struct Builder {
+    py_invoke_handler: PyInvokeHandlerType,
+    gil_scheduler_config: GilSchedulerConfig,
+    py_error_hook: Option<PyObject>,
+    panic_on_py_error: bool,
+    invoke_deadlines: InvokeDeadlines,
+    native_commands: HashSet<String>,
+    rust_commands: HashMap<String, Box<RustCommandHandler>>,
+    origin_policy: OriginPolicy,
+    record_ipc: Option<PathBuf>,
+}
+
+impl Builder {
+    /// See [init] for the requirements of `py_invoke_handler`.
+    pub fn new(py_invoke_handler: PyInvokeHandlerType) -> Self {
+        Self {
+            py_invoke_handler,
+            gil_scheduler_config: Default::default(),
+            py_error_hook: None,
+            panic_on_py_error: false,
+            invoke_deadlines: Default::default(),
+            native_commands: Default::default(),
+            rust_commands: Default::default(),
+            origin_policy: Default::default(),
+            record_ipc: None,
+        }
+    }
+
+    /// Sets how the ipc requests are scheduled onto the GIL, see [GilSchedulerConfig].
+    pub fn gil_scheduler(mut self, config: GilSchedulerConfig) -> Self {
+        self.gil_scheduler_config = config;
+        self
+    }
+
+    /// Sets the hook that will be called when `py_invoke_handler` raises an exception.
+    ///
+    /// The `hook` should have the following signature:
+    ///
+    /// > def hook(exception: BaseException, /) -> None:
+    /// >     ...
+    ///
+    /// The `exception` is a `RuntimeError` whose `__cause__` is the exception raised by `py_invoke_handler`.
+    /// If the `hook` raises an exception too, it will be written to `sys.unraisablehook`.
+    ///
+    /// Before calling the `hook`, if the [Invoke][pytauri_core::ext_mod::ipc::Invoke] has not been consumed yet,
+    /// it will be rejected with the structured exception
+    /// (see [exception_to_json][pytauri_core::ext_mod::ipc::exception_to_json]),
+    /// so the frontend will not wait forever.
+    ///
+    /// If no `hook` is set (default), the exception will be written to `sys.unraisablehook`.
+    pub fn py_error_hook(mut self, hook: PyObject) -> Self {
+        self.py_error_hook = Some(hook);
+        self
+    }
+
+    /// Whether to `panic!` after `py_invoke_handler` raises an exception
+    /// (and after the [Builder::py_error_hook] is called).
+    ///
+    /// Default is `false`.
+    pub fn panic_on_py_error(mut self, panic: bool) -> Self {
+        self.panic_on_py_error = panic;
+        self
+    }
+
+    /// Adds the command names that can be called via `plugin:pytauri|<command>`,
+    /// in addition to the `commands` of the plugin config.
+    ///
+    /// They are dispatched to `py_invoke_handler` with the name in `Invoke.command`.
+    /// By default, no command names are accepted, the others (except `pyfunc`)
+    /// will be rejected by tauri with "command not found".
+    ///
+    /// NOTE: the permissions are only generated for the commands of the plugin config,
+    /// see the [crate-level documentation](crate) for the ACL of these commands.
+    pub fn commands<I, S>(mut self, commands: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.native_commands
+            .extend(commands.into_iter().map(Into::into));
+        self
+    }
+
+    /// Handles `command` with `handler` in Rust instead of `py_invoke_handler`.
+    ///
+    /// The frontend calls it the same way as Python commands (both `pyfunc` and
+    /// `plugin:pytauri|<command>`), so commands can be moved between Python and Rust
+    /// without changing the JS call sites. The origin policy and the ACL are checked
+    /// as for Python commands, then `handler` is called without acquiring the GIL.
+    ///
+    /// `handler` receives the [InvokeMessage](tauri::ipc::InvokeMessage), so it can read
+    /// the body and headers, and returns a future whose output is the response.
+    /// `handler` is called on the ipc thread, so it must not block, do the slow work in the future,
+    /// which is spawned on the tauri async runtime:
+    ///
+    /// ```ignore
+    /// Builder::new(py_invoke_handler).rust_command("add", |message| async move {
+    ///     let [a, b]: [i64; 2] = serde_json::from_slice(match message.payload() {
+    ///         InvokeBody::Raw(raw) => raw,
+    ///         InvokeBody::Json(_) => return Err("expected raw body".into()),
+    ///     })
+    ///     .map_err(InvokeError::from_error)?;
+    ///     Ok(a + b)
+    /// });
+    /// ```
+    ///
+    /// A Rust command takes precedence over the Python command with the same name.
+    ///
+    /// Rust commands are counted in [IpcMetricsExt] (with zero [CommandMetrics::gil_wait])
+    /// and recorded by [Builder::record_ipc] like Python commands,
+    /// but [Builder::invoke_timeout] / [Builder::command_timeout] don't apply to them.
+    pub fn rust_command<F, Fut, T>(mut self, command: impl Into<String>, handler: F) -> Self
+    where
+        F: Fn(tauri::ipc::InvokeMessage<PyTauriRuntime>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<T, tauri::ipc::InvokeError>> + Send + 'static,
+        T: tauri::ipc::IpcResponse,
+    {
+        let handler = move |message| -> RustCommandFuture {
+            let response = handler(message);
+            Box::pin(async move { response.await.into() })
+        };
+        self.rust_commands.insert(command.into(), Box::new(handler));
+        self
+    }
+
+    /// Sets the deadline of all ipc requests.
+    ///
+    /// If the Python side does not respond within `timeout`, the ipc will be rejected,
+    /// and `InvokeResolver.cancelled` will be set to `True` to notify the Python side.
+    ///
+    /// Default is no deadline. See also [Builder::command_timeout].
+    pub fn invoke_timeout(mut self, timeout: Duration) -> Self {
+        self.invoke_deadlines.default = Some(timeout);
+        self
+    }
+
+    /// Sets the deadline of the ipc requests of `command`,
+    /// it takes precedence over [Builder::invoke_timeout].
+    pub fn command_timeout(mut self, command: impl Into<String>, timeout: Duration) -> Self {
+        self.invoke_deadlines
+            .commands
+            .insert(command.into(), timeout);
+        self
+    }
+
+    /// Sets which pages are allowed to call Python commands, see [OriginPolicy].
+    ///
+    /// Default is [OriginPolicy::local_only]. The ipc requests from other pages
+    /// are rejected (and logged) before acquiring the GIL.
+    pub fn origin_policy(mut self, policy: OriginPolicy) -> Self {
+        self.origin_policy = policy;
+        self
+    }
+
+    /// Records every ipc request dispatched to Python (or a [Builder::rust_command]) and its response
+    /// to the JSON lines file at `path` (appending if it exists), see [IpcRecord].
+    ///
+    /// The recordings can be replayed as regression tests with `replay`
+    /// (requires the `test` feature). The requests rejected before being dispatched
+    /// (e.g., by the [OriginPolicy] or the ACL) are not recorded.
+    ///
+    /// # NOTE:
+    ///
+    /// The headers and bodies are recorded as is, so don't enable it in production
+    /// if the ipc carries sensitive data.
+    pub fn record_ipc(mut self, path: impl Into<PathBuf>) -> Self {
+        self.record_ipc = Some(path.into());
+        self
+    }
+
+    /// Builds the plugin.
+    pub fn build(self) -> TauriPlugin<PyTauriRuntime> {
+        let Self {
+            py_invoke_handler,
+            gil_scheduler_config,
+            py_error_hook,
+            panic_on_py_error,
+            invoke_deadlines,
+            native_commands,
+            rust_commands,
+            origin_policy,
+            record_ipc,
+        } = self;
+
+        PluginBuilder::<PyTauriRuntime>::new(PLUGIN_NAME)
+            .invoke_handler(invoke_handler)
+            .on_page_load(|webview, payload| {
+                if payload.event() == PageLoadEvent::Started {
+                    let callbacks =
+                        close_channels(webview, ChannelCloseEvent::PageLoad(webview.label()));
+                    call_channel_on_close_with_gil(webview, callbacks);
+                }
+            })
+            .on_event(|app_handle, event| {
+                if let RunEvent::WindowEvent {
+                    label,
+                    event: WindowEvent::Destroyed,
+                    ..
+                } = event
+                {
+                    let callbacks =
+                        close_channels(app_handle, ChannelCloseEvent::WindowDestroyed(label));
+                    call_channel_on_close_with_gil(app_handle, callbacks);
+                }
+            })
+            .setup(move |app_handle, _plugin_api| {
+                // if false, there has already state set for the app instance.
+                if !app_handle.manage(PyInvokeHandler::new(py_invoke_handler)) {
+                    unreachable!(
+                        "`PyInvokeHandler` is private, so it is impossible for other crates to manage it"
+                    )
+                }
+                if !app_handle.manage(GilScheduler::new(gil_scheduler_config)) {
+                    unreachable!(
+                        "`GilScheduler` is private, so it is impossible for other crates to manage it"
+                    )
+                }
+                if !app_handle.manage(PyInvokeErrorHandler::new(py_error_hook, panic_on_py_error)) {
+                    unreachable!(
+                        "`PyInvokeErrorHandler` is private, so it is impossible for other crates to manage it"
+                    )
+                }
+                if !app_handle.manage(invoke_deadlines) {
+                    unreachable!(
+                        "`InvokeDeadlines` is private, so it is impossible for other crates to manage it"
+                    )
+                }
+                if !app_handle.manage(RustCommands(rust_commands)) {
+                    unreachable!(
+                        "`RustCommands` is private, so it is impossible for other crates to manage it"
+                    )
+                }
+                let mut native_commands = native_commands;
+                native_commands.extend(PluginConfig::from_app(app_handle)?.commands);
+                if !app_handle.manage(NativeCommands(native_commands)) {
+                    unreachable!(
+                        "`NativeCommands` is private, so it is impossible for other crates to manage it"
+                    )
+                }
+                if !app_handle.manage(IpcMetrics::default()) {
+                    unreachable!(
+                        "`IpcMetrics` is private, so it is impossible for other crates to manage it"
+                    )
+                }
+                if !app_handle.manage(OriginPolicyState(origin_policy)) {
+                    unreachable!(
+                        "`OriginPolicyState` is private, so it is impossible for other crates to manage it"
+                    )
+                }
+                if !app_handle.manage(IpcRecorder::new(record_ipc)?) {
+                    unreachable!(
+                        "`IpcRecorder` is private, so it is impossible for other crates to manage it"
+                    )
+                }
+                Ok(())
+            })
+            .build()
+    }
+}
+
+/// Schedules the `on_close` callbacks of the closed `Channel`s, because we are on the event loop thread.
+fn call_channel_on_close_with_gil(
+    manager: &impl Manager<PyTauriRuntime>,
+    callbacks: Vec<PyObject>,
+) {
+    if callbacks.is_empty() {
+        return;
+    }
+    manager
+        .state::<GilScheduler>()
+        .task_with_gil(move |py| call_channel_on_close(py, callbacks));
 }
 
 mod sealed {
@@ -98,13 +521,61 @@ impl From<PyInvokeHandlerStateError> for PyErr {
 
 pub type PyInvokeHandlerStateResult<T> = Result<T, PyInvokeHandlerStateError>;
 
-/// Gets the `py_invoke_handler` passed in when initializing the plugin.
+/// The error returned by [PyInvokeHandlerExt::add_py_invoke_handler].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum PyInvokeHandlerError {
+    State(PyInvokeHandlerStateError),
+    /// The label pattern is not a valid glob pattern.
+    InvalidLabel(String),
+}
+
+impl Display for PyInvokeHandlerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::State(e) => Display::fmt(e, f),
+            Self::InvalidLabel(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl Error for PyInvokeHandlerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::State(e) => Some(e),
+            Self::InvalidLabel(_) => None,
+        }
+    }
+}
+
+impl From<PyInvokeHandlerStateError> for PyInvokeHandlerError {
+    fn from(value: PyInvokeHandlerStateError) -> Self {
+        Self::State(value)
+    }
+}
+
+impl From<PyInvokeHandlerError> for PyErr {
+    fn from(value: PyInvokeHandlerError) -> Self {
+        match value {
+            PyInvokeHandlerError::State(e) => e.into(),
+            PyInvokeHandlerError::InvalidLabel(msg) => PyValueError::new_err(msg),
+        }
+    }
+}
+
+/// Gets and manages the `py_invoke_handler`s of the plugin.
+///
+/// Besides the default `py_invoke_handler` passed in when initializing the plugin,
+/// you can register handlers for some windows or webviews with
+/// [PyInvokeHandlerExt::add_py_invoke_handler], e.g., to expose a different set of commands
+/// to a window showing untrusted content.
 pub trait PyInvokeHandlerExt<R: Runtime>: Manager<R> + sealed::SealedTrait<R> {
+    /// Gets the default `py_invoke_handler` passed in when initializing the plugin.
     fn try_py_invoke_handler(
         &self,
     ) -> PyInvokeHandlerStateResult<impl Deref<Target = PyInvokeHandlerType>> {
         self.try_state::<PyInvokeHandler>()
-            .map(|state| state.inner().deref())
+            .map(|state| state.inner().default.deref())
             .ok_or(PyInvokeHandlerStateError)
     }
 
@@ -114,6 +585,66 @@ pub trait PyInvokeHandlerExt<R: Runtime>: Manager<R> + sealed::SealedTrait<R> {
     fn py_invoke_handler(&self) -> impl Deref<Target = PyInvokeHandlerType> {
         self.try_py_invoke_handler().unwrap()
     }
+
+    /// Gets the `py_invoke_handler` that handles the ipc requests from the window or webview
+    /// with `label`.
+    ///
+    /// The labeled handlers are tried in the order they were added, the first one
+    /// whose pattern matches `label` is returned. If none matches, returns the default handler.
+    ///
+    /// NOTE: the ipc requests from a webview are handled by the handler matching
+    /// the label of the webview or of its window.
+    fn try_py_invoke_handler_for(
+        &self,
+        label: &str,
+    ) -> PyInvokeHandlerStateResult<impl Deref<Target = PyInvokeHandlerType>> {
+        self.try_state::<PyInvokeHandler>()
+            .map(|state| state.inner().resolve(&[label]))
+            .ok_or(PyInvokeHandlerStateError)
+    }
+
+    /// Registers `handler` for the windows or webviews whose label matches `label`,
+    /// which is a glob pattern, e.g., `"main"`, `"preview-*"`.
+    ///
+    /// If there is already a handler registered with the same `label`, it is replaced
+    /// (and keeps its order). The ipc requests that are already dispatched are not affected.
+    fn add_py_invoke_handler(
+        &self,
+        label: &str,
+        handler: PyInvokeHandlerType,
+    ) -> Result<(), PyInvokeHandlerError> {
+        let state = self
+            .try_state::<PyInvokeHandler>()
+            .ok_or(PyInvokeHandlerStateError)?;
+        let pattern = Pattern::new(label).map_err(|e| {
+            PyInvokeHandlerError::InvalidLabel(format!("invalid label pattern `{label}`: {e}"))
+        })?;
+        let handler = Arc::new(handler);
+
+        let mut labeled = state.inner().labeled_mut();
+        match labeled.iter_mut().find(|labeled| labeled.label == label) {
+            Some(labeled) => labeled.handler = handler,
+            None => labeled.push(LabeledPyInvokeHandler {
+                label: label.to_owned(),
+                pattern,
+                handler,
+            }),
+        }
+        Ok(())
+    }
+
+    /// Removes the handler registered with `label` by [PyInvokeHandlerExt::add_py_invoke_handler].
+    ///
+    /// Returns `false` if there is no such handler.
+    fn remove_py_invoke_handler(&self, label: &str) -> PyInvokeHandlerStateResult<bool> {
+        let state = self
+            .try_state::<PyInvokeHandler>()
+            .ok_or(PyInvokeHandlerStateError)?;
+        let mut labeled = state.inner().labeled_mut();
+        let len = labeled.len();
+        labeled.retain(|labeled| labeled.label != label);
+        Ok(labeled.len() != len)
+    }
 }
 
 impl<R: Runtime, T: Manager<R>> PyInvokeHandlerExt<R> for T {}
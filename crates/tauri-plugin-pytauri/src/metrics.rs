@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use pytauri_core::ext_mod::ipc::InvokeOutcome;
+use tauri::ipc::InvokeResponse;
+use tauri::{Manager, Runtime};
+
+/// The metrics of a Python (or Rust) command, see [IpcMetricsExt::ipc_metrics].
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct CommandMetrics {
+    /// The number of ipc requests dispatched to Python (or the Rust command handler).
+    pub invoked: u64,
+    /// The number of ipc requests rejected before being dispatched,
+    /// e.g., by the [OriginPolicy](crate::OriginPolicy) or the ACL.
+    pub denied: u64,
+    /// See [InvokeOutcome::Resolved].
+    pub resolved: u64,
+    /// See [InvokeOutcome::Rejected].
+    pub rejected: u64,
+    /// See [InvokeOutcome::Dropped].
+    pub dropped: u64,
+    /// The total time spent queued for the GIL, always zero for the Rust commands.
+    pub gil_wait: Duration,
+    /// The maximum time spent queued for the GIL.
+    pub max_gil_wait: Duration,
+    /// The total time from acquiring the GIL (or calling the Rust command handler)
+    /// to the ipc being responded.
+    ///
+    /// For async handlers, it includes the time of the whole coroutine.
+    pub run_time: Duration,
+    /// The maximum time from acquiring the GIL to the ipc being responded.
+    pub max_run_time: Duration,
+}
+
+impl CommandMetrics {
+    /// The number of ipc requests that have been dispatched and responded.
+    pub fn completed(&self) -> u64 {
+        self.resolved + self.rejected + self.dropped
+    }
+
+    /// The number of ipc requests that have been dispatched but not responded yet.
+    ///
+    /// NOTE: the requests dispatched before [IpcMetricsExt::reset_ipc_metrics] are not counted
+    /// in [Self::invoked], but are counted in [Self::completed] once responded, so it saturates at zero.
+    pub fn pending(&self) -> u64 {
+        self.invoked.saturating_sub(self.completed())
+    }
+}
+
+/// The metrics of all Python (and Rust) commands, keyed by the command name.
+#[derive(Default, Clone)]
+pub(crate) struct IpcMetrics(Arc<Mutex<HashMap<String, CommandMetrics>>>);
+
+impl IpcMetrics {
+    fn update(&self, command: &str, f: impl FnOnce(&mut CommandMetrics)) {
+        let mut metrics = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        match metrics.get_mut(command) {
+            Some(command_metrics) => f(command_metrics),
+            None => f(metrics.entry(command.to_owned()).or_default()),
+        }
+    }
+
+    fn snapshot(&self) -> HashMap<String, CommandMetrics> {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    fn reset(&self) {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+
+    pub(crate) fn denied(&self, command: &str) {
+        self.update(command, |metrics| metrics.denied += 1);
+    }
+
+    /// Starts timing an ipc request, call it before scheduling the request onto the GIL.
+    pub(crate) fn start(&self, command: String) -> InvokeTimer {
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!(
+            "pyfunc",
+            command = %command,
+            gil_wait_us = tracing::field::Empty,
+            run_time_us = tracing::field::Empty,
+            outcome = tracing::field::Empty,
+        );
+        InvokeTimer {
+            metrics: self.clone(),
+            command,
+            enqueued: Instant::now(),
+            #[cfg(feature = "tracing")]
+            span,
+        }
+    }
+}
+
+/// See [IpcMetrics::start].
+pub(crate) struct InvokeTimer {
+    metrics: IpcMetrics,
+    command: String,
+    enqueued: Instant,
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+impl InvokeTimer {
+    /// Call it once the GIL is acquired, returns the callback for `Invoke::set_on_complete`.
+    pub(crate) fn gil_acquired(
+        self,
+    ) -> impl FnOnce(InvokeOutcome, &InvokeResponse) + Send + 'static {
+        let gil_wait = self.enqueued.elapsed();
+        self.started(gil_wait)
+    }
+
+    /// Same as [InvokeTimer::gil_acquired], but for the Rust commands which don't need the GIL,
+    /// so the GIL wait is zero.
+    pub(crate) fn without_gil(
+        self,
+    ) -> impl FnOnce(InvokeOutcome, &InvokeResponse) + Send + 'static {
+        self.started(Duration::ZERO)
+    }
+
+    fn started(
+        self,
+        gil_wait: Duration,
+    ) -> impl FnOnce(InvokeOutcome, &InvokeResponse) + Send + 'static {
+        let Self {
+            metrics,
+            command,
+            enqueued: _,
+            #[cfg(feature = "tracing")]
+            span,
+        } = self;
+
+        let started = Instant::now();
+        metrics.update(&command, |metrics| {
+            metrics.invoked += 1;
+            metrics.gil_wait += gil_wait;
+            metrics.max_gil_wait = metrics.max_gil_wait.max(gil_wait);
+        });
+        #[cfg(feature = "tracing")]
+        span.record("gil_wait_us", gil_wait.as_micros() as u64);
+
+        move |outcome, _response| {
+            let run_time = started.elapsed();
+            metrics.update(&command, |metrics| {
+                match outcome {
+                    InvokeOutcome::Resolved => metrics.resolved += 1,
+                    InvokeOutcome::Rejected => metrics.rejected += 1,
+                    InvokeOutcome::Dropped => metrics.dropped += 1,
+                }
+                metrics.run_time += run_time;
+                metrics.max_run_time = metrics.max_run_time.max(run_time);
+            });
+            #[cfg(feature = "tracing")]
+            {
+                span.record("run_time_us", run_time.as_micros() as u64);
+                span.record("outcome", tracing::field::debug(outcome));
+                // the span is closed when dropped
+            }
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    pub(crate) fn span(&self) -> &tracing::Span {
+        &self.span
+    }
+}
+
+/// Gets the ipc metrics of the Python (and Rust) commands, see [crate::Builder::rust_command].
+pub trait IpcMetricsExt<R: Runtime>: Manager<R> + crate::sealed::SealedTrait<R> {
+    /// Returns a snapshot of the metrics of each command, keyed by the command name.
+    ///
+    /// Returns an empty map if the plugin is not initialized.
+    fn ipc_metrics(&self) -> HashMap<String, CommandMetrics> {
+        self.try_state::<IpcMetrics>()
+            .map(|metrics| metrics.snapshot())
+            .unwrap_or_default()
+    }
+
+    /// Resets the metrics of all commands.
+    fn reset_ipc_metrics(&self) {
+        if let Some(metrics) = self.try_state::<IpcMetrics>() {
+            metrics.reset();
+        }
+    }
+}
+
+impl<R: Runtime, T: Manager<R>> IpcMetricsExt<R> for T {}
+
+#[cfg(test)]
+mod tests {
+    use tauri::ipc::InvokeResponseBody;
+
+    use super::*;
+
+    fn response() -> InvokeResponse {
+        InvokeResponse::Ok(InvokeResponseBody::Json("null".into()))
+    }
+
+    #[test]
+    fn test_outcomes() {
+        let metrics = IpcMetrics::default();
+        for outcome in [
+            InvokeOutcome::Resolved,
+            InvokeOutcome::Rejected,
+            InvokeOutcome::Dropped,
+        ] {
+            let on_complete = metrics.start("command".to_owned()).gil_acquired();
+            assert_eq!(metrics.snapshot()["command"].pending(), 1);
+            on_complete(outcome, &response());
+        }
+        metrics.denied("command");
+
+        let command = &metrics.snapshot()["command"];
+        assert_eq!(command.invoked, 3);
+        assert_eq!(command.denied, 1);
+        assert_eq!(
+            (command.resolved, command.rejected, command.dropped),
+            (1, 1, 1)
+        );
+        assert_eq!(command.completed(), 3);
+        assert_eq!(command.pending(), 0);
+    }
+
+    #[test]
+    fn test_without_gil() {
+        let metrics = IpcMetrics::default();
+        let on_complete = metrics.start("command".to_owned()).without_gil();
+        assert_eq!(metrics.snapshot()["command"].pending(), 1);
+        on_complete(InvokeOutcome::Resolved, &response());
+
+        let command = &metrics.snapshot()["command"];
+        assert_eq!((command.invoked, command.resolved), (1, 1));
+        assert_eq!(command.gil_wait, Duration::ZERO);
+        assert_eq!(command.max_gil_wait, Duration::ZERO);
+    }
+
+    #[test]
+    fn test_reset_while_in_flight() {
+        let metrics = IpcMetrics::default();
+        let on_complete = metrics.start("command".to_owned()).gil_acquired();
+
+        metrics.reset();
+        assert!(metrics.snapshot().is_empty());
+
+        // the request dispatched before the reset is responded after it
+        on_complete(InvokeOutcome::Resolved, &response());
+        let command = &metrics.snapshot()["command"];
+        assert_eq!(command.invoked, 0);
+        assert_eq!(command.completed(), 1);
+        assert_eq!(command.pending(), 0);
+    }
+}
@@ -0,0 +1,137 @@
+use std::error::Error;
+use std::fmt::{self, Display};
+
+use glob::Pattern;
+use tauri::utils::acl::RemoteUrlPattern;
+use tauri::utils::config::FrontendDist;
+use tauri::{Manager as _, Runtime, Url, Webview};
+
+/// Which pages are allowed to call Python commands.
+///
+/// By default (or [OriginPolicy::local_only]), only the local pages of the app are allowed, i.e.:
+///
+/// - the pages served by the tauri custom protocol (e.g., `tauri://localhost`, `http://tauri.localhost`)
+///   or other custom protocols.
+/// - the pages under `devUrl` (only in dev mode) or the URL `frontendDist`.
+///
+/// Remote pages can be allowed for some windows with [OriginPolicy::allow_remote].
+///
+/// # NOTE:
+///
+/// The ipc message does not carry the URL of the page that sent it,
+/// so the policy is checked against the current URL of the webview.
+#[derive(Debug, Clone, Default)]
+pub struct OriginPolicy {
+    remotes: Vec<RemoteOrigins>,
+}
+
+#[derive(Debug, Clone)]
+struct RemoteOrigins {
+    window: Pattern,
+    urls: Vec<RemoteUrlPattern>,
+}
+
+impl OriginPolicy {
+    /// Only allows the local pages of the app, this is the default.
+    pub fn local_only() -> Self {
+        Self::default()
+    }
+
+    /// Allows the remote pages matching `urls` in the windows whose label matches `window`.
+    ///
+    /// - `window` is a glob pattern, e.g., `"main"`, `"preview-*"`, `"*"`.
+    /// - `urls` are [URL patterns](https://urlpattern.spec.whatwg.org/),
+    ///   the same as the `remote.urls` of tauri capabilities, e.g., `"https://*.example.com"`.
+    ///
+    /// Can be called multiple times, the remote pages are allowed if any of them matches.
+    pub fn allow_remote<I, S>(mut self, window: &str, urls: I) -> Result<Self, OriginPolicyError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let window = Pattern::new(window)
+            .map_err(|e| OriginPolicyError(format!("invalid window pattern `{window}`: {e}")))?;
+        let urls = urls
+            .into_iter()
+            .map(|url| {
+                let url = url.as_ref();
+                url.parse::<RemoteUrlPattern>()
+                    .map_err(|e| OriginPolicyError(format!("invalid URL pattern `{url}`: {e}")))
+            })
+            .collect::<Result<_, _>>()?;
+        self.remotes.push(RemoteOrigins { window, urls });
+        Ok(self)
+    }
+
+    /// Checks if the current page of `webview` is allowed to call Python commands.
+    ///
+    /// Returns the URL of the page if it's not allowed.
+    pub(crate) fn check<R: Runtime>(&self, webview: &Webview<R>) -> Result<(), String> {
+        let url = match webview.url() {
+            Ok(url) => url,
+            // Fail closed, we don't know where the ipc comes from.
+            Err(e) => return Err(format!("<unknown: {e}>")),
+        };
+
+        if is_local_url(webview, &url) {
+            return Ok(());
+        }
+
+        let window = webview.window_ref();
+        let label = window.label();
+        let allowed = self
+            .remotes
+            .iter()
+            .filter(|remote| remote.window.matches(label))
+            .any(|remote| remote.urls.iter().any(|pattern| pattern.test(&url)));
+        if allowed {
+            Ok(())
+        } else {
+            Err(url.into())
+        }
+    }
+}
+
+/// The [OriginPolicy] of the plugin, see [crate::Builder::origin_policy].
+pub(crate) struct OriginPolicyState(pub(crate) OriginPolicy);
+
+/// The error returned by [OriginPolicy::allow_remote] when a pattern is invalid.
+#[derive(Debug)]
+pub struct OriginPolicyError(String);
+
+impl Display for OriginPolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Error for OriginPolicyError {}
+
+/// Mirrors the (private) `is_local_url` of tauri, which is used to resolve the ACL.
+fn is_local_url<R: Runtime>(webview: &Webview<R>, url: &Url) -> bool {
+    // from the custom protocols, including `tauri://`:
+    // - `<scheme>://localhost/` on macOS and Linux
+    // - `http(s)://<scheme>.localhost/` on Windows and Android
+    let is_custom_protocol = match url.scheme() {
+        "http" | "https" => url
+            .domain()
+            .is_some_and(|domain| domain.ends_with(".localhost")),
+        _ => url.domain() == Some("localhost"),
+    };
+    if is_custom_protocol {
+        return true;
+    }
+
+    // or relative to `devUrl` or `frontendDist`
+    let build_config = &webview.config().build;
+    let base_url = if tauri::is_dev() {
+        build_config.dev_url.as_ref()
+    } else {
+        None
+    }
+    .or(match &build_config.frontend_dist {
+        Some(FrontendDist::Url(url)) => Some(url),
+        _ => None,
+    });
+    base_url.is_some_and(|base_url| base_url.make_relative(url).is_some())
+}
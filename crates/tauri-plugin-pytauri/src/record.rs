@@ -0,0 +1,320 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead as _, BufReader, BufWriter, Write as _};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Sender};
+use std::{iter, thread};
+
+use serde::{Deserialize, Serialize};
+use tauri::http::HeaderMap;
+use tauri::ipc::{InvokeBody, InvokeResponse, InvokeResponseBody};
+use tauri::{Runtime, Webview};
+
+/// An ipc request dispatched to Python (or a Rust command, see [crate::Builder::rust_command])
+/// and its response,
+/// it's recorded as one line in the JSON lines file of [crate::Builder::record_ipc].
+///
+/// Use [IpcRecord::read_jsonl] to load the recordings, and `replay` (requires the `test` feature)
+/// to replay them in a [MockRuntime](tauri::test::MockRuntime) app.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct IpcRecord {
+    /// The label of the webview that sent the ipc.
+    pub webview: String,
+    /// The tauri command without the `plugin:pytauri|` prefix, i.e., `pyfunc` or the native command.
+    pub cmd: String,
+    /// The name of the Python (or Rust) command.
+    pub command: String,
+    /// The headers of the ipc, the non UTF-8 values are converted lossily.
+    pub headers: Vec<(String, String)>,
+    pub body: RecordedBody,
+    pub response: RecordedResponse,
+}
+
+/// See [tauri::ipc::InvokeBody] and [tauri::ipc::InvokeResponseBody].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordedBody {
+    Json(serde_json::Value),
+    Raw(Vec<u8>),
+}
+
+/// How the ipc is responded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordedResponse {
+    Resolved(RecordedBody),
+    Rejected(serde_json::Value),
+}
+
+impl From<&InvokeBody> for RecordedBody {
+    fn from(body: &InvokeBody) -> Self {
+        match body {
+            InvokeBody::Json(json) => Self::Json(json.clone()),
+            InvokeBody::Raw(raw) => Self::Raw(raw.clone()),
+        }
+    }
+}
+
+impl From<RecordedBody> for InvokeBody {
+    fn from(body: RecordedBody) -> Self {
+        match body {
+            RecordedBody::Json(json) => Self::Json(json),
+            RecordedBody::Raw(raw) => Self::Raw(raw),
+        }
+    }
+}
+
+impl From<&InvokeResponseBody> for RecordedBody {
+    fn from(body: &InvokeResponseBody) -> Self {
+        match body {
+            // NOTE: Python can resolve with any `str`, so it may not be valid JSON,
+            // in which case we record it as a JSON string.
+            InvokeResponseBody::Json(json) => {
+                Self::Json(serde_json::from_str(json).unwrap_or_else(|_| json.as_str().into()))
+            }
+            InvokeResponseBody::Raw(raw) => Self::Raw(raw.clone()),
+        }
+    }
+}
+
+impl From<&InvokeResponse> for RecordedResponse {
+    fn from(response: &InvokeResponse) -> Self {
+        match response {
+            InvokeResponse::Ok(body) => Self::Resolved(body.into()),
+            InvokeResponse::Err(error) => Self::Rejected(error.0.clone()),
+        }
+    }
+}
+
+impl IpcRecord {
+    /// Reads the recordings from the JSON lines file written by [crate::Builder::record_ipc].
+    ///
+    /// Empty lines are skipped.
+    pub fn read_jsonl(path: impl AsRef<Path>) -> io::Result<Vec<Self>> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut records = Vec::new();
+        for (lineno, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record = serde_json::from_str(&line).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid ipc record at line {}: {e}", lineno + 1),
+                )
+            })?;
+            records.push(record);
+        }
+        Ok(records)
+    }
+}
+
+/// Writes the [IpcRecord]s on a background thread, see [crate::Builder::record_ipc].
+///
+/// [None] means the recording is disabled.
+pub(crate) struct IpcRecorder(Option<Sender<IpcRecord>>);
+
+impl IpcRecorder {
+    pub(crate) fn new(path: Option<PathBuf>) -> io::Result<Self> {
+        let Some(path) = path else {
+            return Ok(Self(None));
+        };
+
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let (sender, receiver) = channel::<IpcRecord>();
+        thread::Builder::new()
+            .name("pytauri-ipc-recorder".to_owned())
+            .spawn(move || {
+                let mut writer = BufWriter::new(file);
+                while let Ok(record) = receiver.recv() {
+                    // flush once there are no more pending records,
+                    // so the file is complete even if the app crashes later.
+                    let result = iter::once(record)
+                        .chain(receiver.try_iter())
+                        .try_for_each(|record| {
+                            serde_json::to_writer(&mut writer, &record)?;
+                            writer.write_all(b"\n")
+                        })
+                        .and_then(|_| writer.flush());
+                    if let Err(e) = result {
+                        log::error!("Failed to record the ipc to `{}`: {e}", path.display());
+                    }
+                }
+            })?;
+        Ok(Self(Some(sender)))
+    }
+
+    /// Starts recording an ipc, returns [None] if the recording is disabled.
+    pub(crate) fn start<R: Runtime>(
+        &self,
+        webview: &Webview<R>,
+        cmd: &str,
+        command: &str,
+        headers: &HeaderMap,
+        body: &InvokeBody,
+    ) -> Option<PendingRecord> {
+        let sender = self.0.as_ref()?.clone();
+        let headers = headers
+            .iter()
+            .map(|(name, value)| {
+                (
+                    name.as_str().to_owned(),
+                    String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                )
+            })
+            .collect();
+        Some(PendingRecord {
+            sender,
+            webview: webview.label().to_owned(),
+            cmd: cmd.to_owned(),
+            command: command.to_owned(),
+            headers,
+            body: body.into(),
+        })
+    }
+}
+
+/// The request part of an [IpcRecord], see [IpcRecorder::start].
+pub(crate) struct PendingRecord {
+    sender: Sender<IpcRecord>,
+    webview: String,
+    cmd: String,
+    command: String,
+    headers: Vec<(String, String)>,
+    body: RecordedBody,
+}
+
+impl PendingRecord {
+    /// Sends the record to the writer thread, it doesn't block.
+    pub(crate) fn finish(self, response: &InvokeResponse) {
+        let Self {
+            sender,
+            webview,
+            cmd,
+            command,
+            headers,
+            body,
+        } = self;
+        // the writer thread only exits when all senders are dropped
+        let _ = sender.send(IpcRecord {
+            webview,
+            cmd,
+            command,
+            headers,
+            body,
+            response: response.into(),
+        });
+    }
+}
+
+#[cfg(feature = "test")]
+mod replay {
+    use std::fmt::{self, Display};
+
+    use tauri::http::{HeaderName, HeaderValue};
+    use tauri::ipc::CallbackFn;
+    use tauri::test::{get_ipc_response, MockRuntime, INVOKE_KEY};
+    use tauri::webview::InvokeRequest;
+    use tauri::Manager;
+
+    use super::*;
+    use crate::PLUGIN_NAME;
+
+    /// An [IpcRecord] whose replayed response differs from the recorded one, see [replay].
+    #[derive(Debug, Clone)]
+    #[non_exhaustive]
+    pub struct ReplayMismatch {
+        /// The index of the record in the replayed records.
+        pub index: usize,
+        pub record: IpcRecord,
+        /// [None] if there is no webview window labeled [IpcRecord::webview].
+        pub actual: Option<RecordedResponse>,
+    }
+
+    impl Display for ReplayMismatch {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let Self {
+                index,
+                record,
+                actual,
+            } = self;
+            let to_json = |response: &RecordedResponse| {
+                serde_json::to_string(response).unwrap_or_else(|e| format!("<{e}>"))
+            };
+            write!(
+                f,
+                "ipc record #{index} (`{}` in webview `{}`): expected {}, ",
+                record.command,
+                record.webview,
+                to_json(&record.response)
+            )?;
+            match actual {
+                Some(actual) => write!(f, "got {}", to_json(actual)),
+                None => write!(f, "but there is no such webview window"),
+            }
+        }
+    }
+
+    /// Replays the `records` in order and returns the ones whose responses differ.
+    ///
+    /// The webview windows with the recorded labels must have been created,
+    /// and the ipc requests are sent from the local page of the app.
+    pub fn replay<'a, M, I>(manager: &M, records: I) -> Vec<ReplayMismatch>
+    where
+        M: Manager<MockRuntime>,
+        I: IntoIterator<Item = &'a IpcRecord>,
+    {
+        // see: <https://github.com/tauri-apps/tauri/blob/e3b0260871008e4d213a6036690198ea637d555b/crates/tauri/src/manager/mod.rs#L354>
+        const URL: &str = {
+            if cfg!(windows) {
+                "http://tauri.localhost/"
+            } else {
+                "tauri://localhost/"
+            }
+        };
+
+        let mut mismatches = Vec::new();
+        for (index, record) in records.into_iter().enumerate() {
+            let actual = manager.get_webview_window(&record.webview).map(|webview| {
+                let headers = record
+                    .headers
+                    .iter()
+                    .filter_map(|(name, value)| {
+                        Some((
+                            HeaderName::try_from(name).ok()?,
+                            HeaderValue::try_from(value).ok()?,
+                        ))
+                    })
+                    .collect();
+                let response = get_ipc_response(
+                    &webview,
+                    InvokeRequest {
+                        cmd: format!("plugin:{PLUGIN_NAME}|{}", record.cmd),
+                        callback: CallbackFn(0),
+                        error: CallbackFn(1),
+                        url: URL.parse().unwrap(),
+                        body: record.body.clone().into(),
+                        headers,
+                        invoke_key: INVOKE_KEY.to_string(),
+                    },
+                );
+                match response {
+                    Ok(body) => RecordedResponse::Resolved((&body).into()),
+                    Err(error) => RecordedResponse::Rejected(error),
+                }
+            });
+            if actual.as_ref() != Some(&record.response) {
+                mismatches.push(ReplayMismatch {
+                    index,
+                    record: record.clone(),
+                    actual,
+                });
+            }
+        }
+        mismatches
+    }
+}
+
+#[cfg(feature = "test")]
+pub use replay::{replay, ReplayMismatch};
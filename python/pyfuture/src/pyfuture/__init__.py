@@ -2,7 +2,7 @@
 # ruff: noqa: D104, D101, D107, D105
 
 from collections import deque
-from collections.abc import Awaitable, Generator
+from collections.abc import Awaitable, Callable, Generator
 from contextlib import AsyncExitStack, contextmanager
 from threading import get_ident
 from types import TracebackType
@@ -14,7 +14,14 @@ from typing import (
 )
 from weakref import ReferenceType, ref
 
-from anyio import CancelScope, create_task_group, get_cancelled_exc_class
+import sniffio
+from anyio import (
+    CancelScope,
+    Event,
+    create_task_group,
+    fail_after,
+    get_cancelled_exc_class,
+)
 from anyio.abc import TaskGroup
 from anyio.from_thread import BlockingPortal, start_blocking_portal
 from exceptiongroup import BaseExceptionGroup
@@ -35,13 +42,27 @@ class _PyFutureProto(Protocol, Generic[T]):
 
 
 class _CancelHandleProto(Protocol):
-    def __call__(self) -> None: ...
+    def __call__(self, reason: Optional[str] = None, /) -> None:
+        """`reason` is set as the argument of the cancellation exception passed to Rust if given.
+
+        The exception propagating in Python is not modified.
+        """
 
 
 class _PyRunnerProto(Protocol):
     def __call__(self, py_future: _PyFutureProto[Any], /) -> _CancelHandleProto: ...
 
 
+class _RustFutureProto(Protocol, Generic[T]):
+    """The Rust `pyfuture::awaitable::RustAwaitable`."""
+
+    def poll(self, wake: Callable[[], None], /) -> bool: ...
+
+    def result(self) -> T: ...
+
+    def cancel(self) -> None: ...
+
+
 class _RunnerProto(Protocol):
     def __new__(cls, py_runner: _PyRunnerProto, /) -> Self: ...
 
@@ -90,6 +111,26 @@ class _RunnerStack(Generic[_RunnerTypeVar]):
             ) from exc
 
 
+def _cancelled_with_reason(
+    cancelled: BaseException, reason: Optional[str]
+) -> BaseException:
+    """Return a new cancellation exception with `reason` as its argument, for the Rust side.
+
+    NOTE: DO NOT modify `cancelled` itself, it's still propagating,
+    and the async library may recognize it by its args
+    (e.g., `anyio` checks the message of its cancel scope).
+    """
+    if reason is None:
+        return cancelled
+    try:
+        exc = type(cancelled)(reason)
+    except TypeError:
+        # e.g., `trio.Cancelled` has no public constructor
+        return cancelled
+    exc.__cause__ = cancelled
+    return exc
+
+
 class _PyRunner:
     def __init__(
         self,
@@ -114,6 +155,7 @@ class _PyRunner:
             # its performance is worse than `scope`;
             # use `scope` for cancellation instead.
             scope = CancelScope()
+            cancel_reason: Optional[str] = None
 
             async def _wrapped() -> None:
                 is_cancelled = False
@@ -121,7 +163,12 @@ class _PyRunner:
                     try:
                         result = await py_future.awaitable
                     except BaseException as e:
-                        py_future.set_exception(e)
+                        if isinstance(e, cancelled_exc_class):
+                            py_future.set_exception(
+                                _cancelled_with_reason(e, cancel_reason)
+                            )
+                        else:
+                            py_future.set_exception(e)
                         # NOTE: MUST re-raise `Cancelled` for `CancelScope`;
                         # NOTE: BUT DO NOT raise other exceptions, or it will be caught by `TaskGroup`,
                         # then `TaskGroup` will cancel all other tasks.
@@ -143,7 +190,9 @@ class _PyRunner:
             # so it's thread-safe.
             task_group.start_soon(_wrapped, name="rust_future on event loop thread")
 
-            def cancel() -> None:
+            def cancel(reason: Optional[str] = None, /) -> None:
+                nonlocal cancel_reason
+                cancel_reason = reason
                 if event_loop_thread_id == get_ident():
                     # only the thread that created the `TaskGroup` can run following code,
                     # so it's thread-safe.
@@ -156,12 +205,16 @@ class _PyRunner:
                         scope.cancel, name="cancel rust_future on external thread"
                     )
         else:
+            cancel_reason: Optional[str] = None
 
             async def _wrapped() -> None:
                 try:
                     result = await py_future.awaitable
                 except BaseException as e:
-                    py_future.set_exception(e)
+                    if isinstance(e, cancelled_exc_class):
+                        py_future.set_exception(_cancelled_with_reason(e, cancel_reason))
+                    else:
+                        py_future.set_exception(e)
                     # NOTE: MUST re-raise `Cancelled` for `TaskGroup`;
                     # NOTE: BUT DO NOT raise other exceptions, or it will be caught by `TaskGroup`,
                     # then `TaskGroup` will cancel all other tasks.
@@ -183,7 +236,9 @@ class _PyRunner:
                 _wrapped, name="rust_future on external thread"
             )
 
-            def cancel() -> None:
+            def cancel(reason: Optional[str] = None, /) -> None:
+                nonlocal cancel_reason
+                cancel_reason = reason
                 # whatever the thread is (event loop or external),
                 # rust will use `&mut` to make sure only one thread can cancel the future at a time,
                 # so this code thread-safe.
@@ -232,6 +287,67 @@ class RunnerBuilder:
         return runner
 
 
+async def _with_timeout(awaitable: Awaitable[T], timeout: float, /) -> T:
+    """The implementation of `Runner::future_with_timeout`.
+
+    Raise `TimeoutError` if `awaitable` is not done within `timeout` seconds.
+    """
+    with fail_after(timeout):
+        return await awaitable
+
+
+def _threadsafe_caller() -> Callable[[Callable[[], None]], None]:
+    """Return a function that schedules a callback into the current event loop from any thread."""
+    library = sniffio.current_async_library()
+    if library == "asyncio":
+        import asyncio
+
+        loop = asyncio.get_running_loop()
+
+        def call_soon_threadsafe(callback: Callable[[], None]) -> None:
+            try:
+                loop.call_soon_threadsafe(callback)
+            except RuntimeError:
+                # the event loop is closed, so there is no one waiting for it
+                pass
+
+        return call_soon_threadsafe
+    elif library == "trio":
+        import trio
+
+        token = trio.lowlevel.current_trio_token()
+
+        def run_sync_soon(callback: Callable[[], None]) -> None:
+            try:
+                token.run_sync_soon(callback)
+            except trio.RunFinishedError:
+                # the event loop is closed, so there is no one waiting for it
+                pass
+
+        return run_sync_soon
+    else:
+        raise RuntimeError(f"Unsupported async library: {library}")
+
+
+async def _await_rust_future(rust_future: _RustFutureProto[T]) -> T:
+    """The implementation of `RustAwaitable.__await__`.
+
+    The Rust future is polled on the current event loop thread,
+    and its waker schedules the next poll thread-safely.
+    If the current task is cancelled, the Rust future will be dropped.
+    """
+    call_soon_threadsafe = _threadsafe_caller()
+    while True:
+        woken = Event()
+        if rust_future.poll(lambda: call_soon_threadsafe(woken.set)):
+            return rust_future.result()
+        try:
+            await woken.wait()
+        except BaseException:
+            rust_future.cancel()
+            raise
+
+
 @contextmanager
 def create_runner_builder(
     backend: str = "asyncio", backend_options: Optional[dict[str, Any]] = None
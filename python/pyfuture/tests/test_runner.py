@@ -1,7 +1,7 @@
 # TODO:
 # ruff: noqa: D100, D103
 
-from collections.abc import Awaitable
+from collections.abc import Awaitable, Callable
 from typing import Optional
 
 import pytest
@@ -13,6 +13,11 @@ from pyfuture import (
 from typing_extensions import Self
 
 
+@pytest.fixture(params=["asyncio", "trio"])
+def anyio_backend(request: pytest.FixtureRequest) -> str:
+    return request.param
+
+
 @pytest.mark.anyio
 async def test_runner_builder() -> None:
     class Result:
@@ -78,3 +83,42 @@ async def test_runner_builder() -> None:
         )
 
     await main()
+
+
+@pytest.mark.anyio
+async def test_await_rust_future() -> None:
+    from threading import Thread
+
+    from pyfuture import (
+        _await_rust_future,  # pyright: ignore[reportPrivateUsage]
+    )
+
+    class Result:
+        pass
+
+    mock_result = Result()
+
+    class MockRustFuture:
+        """Becomes ready after being woken from another thread."""
+
+        def __init__(self) -> None:
+            self.polled = 0
+            self.cancelled = False
+
+        def poll(self, wake: Callable[[], None], /) -> bool:
+            self.polled += 1
+            if self.polled > 1:
+                return True
+            Thread(target=wake).start()
+            return False
+
+        def result(self) -> Result:
+            return mock_result
+
+        def cancel(self) -> None:
+            self.cancelled = True
+
+    rust_future = MockRustFuture()
+    assert await _await_rust_future(rust_future) is mock_result
+    assert rust_future.polled == 2
+    assert not rust_future.cancelled
@@ -1,8 +1,9 @@
 """[tauri::ipc](https://docs.rs/tauri/latest/tauri/ipc/index.html)"""
 
-from typing import TYPE_CHECKING, Annotated, Any, Generic, Optional, Union, final
+from collections.abc import Callable, Iterable, Iterator, Mapping
+from typing import TYPE_CHECKING, Annotated, Any, Generic, Literal, Optional, Union, final
 
-from typing_extensions import TypeAliasType, TypedDict, TypeVar
+from typing_extensions import Buffer, Self, TypeAliasType, TypedDict, TypeVar, overload
 
 from pytauri.ffi._ext_mod import pytauri_mod
 
@@ -23,34 +24,31 @@ _ipc_mod = pytauri_mod.ipc
 if TYPE_CHECKING:
     from pytauri.ffi.lib import AppHandle
     from pytauri.ffi.webview import Webview, WebviewWindow
+    from pytauri.ffi.window import Window
 
-_InvokeResponseBody = TypeAliasType("_InvokeResponseBody", Union[str, bytes])
+_InvokeResponseBody = TypeAliasType("_InvokeResponseBody", Union[str, bytes, Buffer])
 """The body of an IPC response.
 
 - str: InvokeResponseBody:Json (Any)
 - bytes: InvokeResponseBody:Raw (ArrayBuffer)
+- Buffer: InvokeResponseBody:Raw (ArrayBuffer), any object supporting the buffer protocol,
+    e.g., `bytearray`, `memoryview`, `numpy.ndarray`. The underlying bytes are copied once
+    without holding the GIL, so do not modify the buffer in other threads at the same time.
 """
 
-Headers = TypeAliasType("Headers", list[tuple[bytes, bytes]])
-"""[http::header::HeaderMap::iter](https://docs.rs/http/latest/http/header/struct.HeaderMap.html#method.iter)
+_HeaderLike = TypeAliasType("_HeaderLike", Union[str, bytes])
+"""A header name or value."""
 
-`(key, value)` pairs of headers.
+_T = TypeVar("_T")
 
-> Each key will be yielded once per associated value.
-> So, if a key has 3 associated values, it will be yielded 3 times.
+_InvokeRejection = TypeAliasType("_InvokeRejection", Union[str, bytes, Any])
+"""The value of an IPC rejection.
 
-```python
-[(b"key0", b"value00"), (b"key0", b"value01"), (b"key1", b"value1")]
-```
-
-!!! tip
-    You can use libraries like [multidict][] or [httpx.Headers] to convert it into dict
-    for more efficient retrieval of a specific header.
-
-    [httpx.Headers]: https://www.python-httpx.org/api/#headers
+- str: message string (or JSON string if `json=True`)
+- bytes: JSON bytes
+- Any: any object that can be converted to a JSON value
 """
 
-
 class State:
     """A marker for state in [ArgumentsType][pytauri.ffi.ipc.ArgumentsType].
 
@@ -85,25 +83,72 @@ class ParametersType(TypedDict, total=False, closed=True):
 
     body: Any
     """Whatever. We just use the `key`, not the `value`."""
+    body_format: Literal["bytes", "object"]
+    """How the `body` is passed, defaults to `"bytes"`.
+
+    It works for both `pyInvoke` (`ArrayBuffer`) and the plain `invoke()` (JSON) of `@tauri-apps/api`,
+    so the handler will get the same type regardless of the transport.
+
+    - `"bytes"`: the raw body, or the serialized JSON bytes of the JSON body.
+    - `"object"`: the JSON value converted to python object, the raw body will be deserialized as JSON first.
+    """
     app_handle: Any
     """Whatever. We just use the `key`, not the `value`."""
     webview_window: Any
     """Whatever. We just use the `key`, not the `value`."""
     headers: Any
     """Whatever. We just use the `key`, not the `value`."""
+    webview: Any
+    """Whatever. We just use the `key`, not the `value`."""
+    window: Any
+    """Whatever. We just use the `key`, not the `value`."""
+    url: Any
+    """Whatever. We just use the `key`, not the `value`."""
+    origin: Any
+    """Whatever. We just use the `key`, not the `value`."""
+    command_scope: Any
+    """Whatever. We just use the `key`, not the `value`."""
+    global_scope: Any
+    """Whatever. We just use the `key`, not the `value`."""
     states: dict[str, type[Any]]
     """A dictionary of state classes."""
 
 
 class _BaseArgumentsType(TypedDict, total=False):
     body: bytes
-    """The body of this ipc message."""
+    """The body of this ipc message.
+
+    If [ParametersType.body_format][pytauri.ffi.ipc.ParametersType.body_format] is `"object"`,
+    it is the JSON value converted to python object instead.
+    """
     app_handle: "AppHandle"
     """The handle of the app."""
     webview_window: "WebviewWindow"
     """The `WebviewWindow` of this `Invoke`."""
-    headers: Headers
+    headers: "Headers"
     """The headers of this ipc message."""
+    webview: "Webview"
+    """The `Webview` of this `Invoke`."""
+    window: "Window"
+    """The `Window` that the `Webview` of this `Invoke` belongs to."""
+    url: str
+    """The current URL of the `Webview` of this `Invoke`.
+
+    NOTE: The ipc message does not carry the URL of the page that sent it,
+    so this is the URL of the webview at the time the invoke is handled.
+    """
+    origin: str
+    """The origin of [url][pytauri.ffi.ipc._BaseArgumentsType.url], e.g., `https://example.com`, `tauri://localhost`."""
+    command_scope: dict[str, list[Any]]
+    """The ACL command scope of this `Invoke`, i.e., `{"allow": [...], "deny": [...]}`.
+
+    See [tauri::ipc::CommandScope](https://docs.rs/tauri/latest/tauri/ipc/struct.CommandScope.html).
+    """
+    global_scope: dict[str, list[Any]]
+    """The ACL global scope of this `Invoke`, i.e., `{"allow": [...], "deny": [...]}`.
+
+    See [tauri::ipc::GlobalScope](https://docs.rs/tauri/latest/tauri/ipc/struct.GlobalScope.html).
+    """
 
 
 class ResolvedArgumentsType(_BaseArgumentsType, total=False):
@@ -134,7 +179,11 @@ if TYPE_CHECKING:
 
     @final
     class Invoke:
-        """[tauri::ipc::Invoke](https://docs.rs/tauri/latest/tauri/ipc/struct.Invoke.html)"""
+        """[tauri::ipc::Invoke](https://docs.rs/tauri/latest/tauri/ipc/struct.Invoke.html)
+
+        If it is dropped without being consumed, the command will be rejected automatically
+        with `"python handler dropped the request without responding"`.
+        """
 
         @property
         def command(self) -> str:
@@ -149,6 +198,9 @@ if TYPE_CHECKING:
             If the frontend illegally calls the IPC,
             this method will automatically reject this `Invoke` and return `None`.
 
+            If the returned `InvokeResolver` is dropped without being resolved or rejected,
+            the command will be rejected automatically.
+
             The return value [InvokeResolver.arguments][pytauri.ffi.ipc.InvokeResolver.arguments]
             is not the same object as the input `parameters`.
             """
@@ -160,12 +212,50 @@ if TYPE_CHECKING:
                 value: The value to resolve the command with.
 
                     - If `str`, it will be serialized as JSON on the frontend.
-                    - If `bytes`, it will be sent as `ArrayBuffer` to the frontend.
+                    - If `bytes` or other `Buffer`, it will be sent as `ArrayBuffer` to the frontend.
             """
             ...
 
-        def reject(self, value: str) -> None:
-            """Consumes this `Invoke` and rejects the command with the given value."""
+        def reject(self, value: _InvokeRejection, /, *, json: bool = False) -> None:
+            """Consumes this `Invoke` and rejects the command with the given value.
+
+            Args:
+                value: The value to reject the command with.
+
+                    - If `str`, it will be sent to the frontend as is (a JSON string),
+                        unless `json` is `True`.
+                    - If `bytes`, it will be deserialized as JSON, and the JSON value will be
+                        sent to the frontend.
+                    - Otherwise, it will be converted to a JSON value by [pythonize](https://github.com/davidhewitt/pythonize),
+                        e.g., `{"code": 404, "message": "not found"}`.
+                json: If `True`, `str` value will be deserialized as JSON.
+
+            Raises:
+                ValueError: If `value` can not be converted to a JSON value.
+            """
+            ...
+
+        def reject_exception(
+            self, exception: BaseException, /, *, traceback: Optional[bool] = None
+        ) -> None:
+            """Consumes this `Invoke` and rejects the command with the given exception.
+
+            The exception will be sent to the frontend as the following JSON object:
+
+            ```json
+            {
+                "type": "<qualname of the exception type>",
+                "message": "<str(exception)>",
+                "args": ["<exception.args, or their repr if they are not serializable>"],
+                "traceback": "<only if `traceback` is true>"
+            }
+            ```
+
+            Args:
+                exception: The exception to reject the command with.
+                traceback: Whether to include the traceback.
+                    Defaults to [IS_DEV][pytauri.IS_DEV], because the traceback may leak information.
+            """
             ...
 
     @final
@@ -177,6 +267,20 @@ if TYPE_CHECKING:
             """The bound arguments of the current command."""
             ...
 
+        @property
+        def cancelled(self) -> bool:
+            """Whether the command has been cancelled (e.g., its deadline has passed).
+
+            If `True`, the command has already been rejected,
+            so you can stop handling it, and calling `resolve` or `reject` will raise `RuntimeError`.
+
+            NOTE: the command may be cancelled on another thread at any time,
+            so don't check it before responding, which is a race condition.
+            Instead, respond first, and check it after `RuntimeError` is raised:
+            it's guaranteed to be `True` if the failure was caused by the cancellation.
+            """
+            ...
+
         def resolve(self, value: _InvokeResponseBody) -> None:
             """Consumes this `InvokeResolver` and resolves the command with the given value.
 
@@ -184,11 +288,110 @@ if TYPE_CHECKING:
                 value: The value to resolve the command with.
 
                     - If `str`, it will be serialized as JSON on the frontend.
-                    - If `bytes`, it will be sent as `ArrayBuffer` to the frontend.
+                    - If `bytes` or other `Buffer`, it will be sent as `ArrayBuffer` to the frontend.
+            """
+
+        def reject(self, value: _InvokeRejection, /, *, json: bool = False) -> None:
+            """Consumes this `InvokeResolver` and rejects the command with the given value.
+
+            Args:
+                value: The value to reject the command with.
+
+                    - If `str`, it will be sent to the frontend as is (a JSON string),
+                        unless `json` is `True`.
+                    - If `bytes`, it will be deserialized as JSON, and the JSON value will be
+                        sent to the frontend.
+                    - Otherwise, it will be converted to a JSON value by [pythonize](https://github.com/davidhewitt/pythonize),
+                        e.g., `{"code": 404, "message": "not found"}`.
+                json: If `True`, `str` value will be deserialized as JSON.
+
+            Raises:
+                ValueError: If `value` can not be converted to a JSON value.
             """
+            ...
+
+        def reject_exception(
+            self, exception: BaseException, /, *, traceback: Optional[bool] = None
+        ) -> None:
+            """Consumes this `InvokeResolver` and rejects the command with the given exception.
+
+            The exception will be sent to the frontend as the following JSON object:
 
-        def reject(self, value: str) -> None:
-            """Consumes this `InvokeResolver` and rejects the command with the given value."""
+            ```json
+            {
+                "type": "<qualname of the exception type>",
+                "message": "<str(exception)>",
+                "args": ["<exception.args, or their repr if they are not serializable>"],
+                "traceback": "<only if `traceback` is true>"
+            }
+            ```
+
+            Args:
+                exception: The exception to reject the command with.
+                traceback: Whether to include the traceback.
+                    Defaults to [IS_DEV][pytauri.IS_DEV], because the traceback may leak information.
+            """
+            ...
+
+    @final
+    class Headers:
+        """[http::header::HeaderMap](https://docs.rs/http/latest/http/header/struct.HeaderMap.html)
+
+        The header names are case-insensitive, and can be `str` or `bytes`.
+        The header values are converted to `bytes` only when they are accessed.
+
+        ```python
+        headers = Headers([("Key0", "value00"), ("key0", b"value01"), ("key1", "value1")])
+        assert headers.get("KEY0") == b"value00"
+        assert headers.get_all(b"key0") == [b"value00", b"value01"]
+        assert "key1" in headers
+        assert list(headers) == [(b"key0", b"value00"), (b"key0", b"value01"), (b"key1", b"value1")]
+        assert headers.to_dict() == {b"key0": [b"value00", b"value01"], b"key1": [b"value1"]}
+        ```
+        """
+
+        def __new__(
+            cls,
+            headers: Union[
+                Mapping[_HeaderLike, _HeaderLike],
+                Iterable[tuple[_HeaderLike, _HeaderLike]],
+                None,
+            ] = None,
+            /,
+        ) -> Self:
+            """
+            Raises:
+                ValueError: If a header name or value is invalid.
+            """
+            ...
+
+        @overload
+        def get(self, key: _HeaderLike, /) -> Optional[bytes]: ...
+        @overload
+        def get(self, key: _HeaderLike, default: _T, /) -> Union[bytes, _T]: ...
+        def get(self, key: _HeaderLike, default: Any = None, /) -> Any:
+            """Returns the first value of `key`, or `default` if there is no such header."""
+            ...
+
+        def get_all(self, key: _HeaderLike, /) -> list[bytes]:
+            """Returns all values of `key`, in the order they were added."""
+            ...
+
+        def to_dict(self, /) -> dict[bytes, list[bytes]]:
+            """Converts the headers into a `dict`, the keys are lowercase."""
+            ...
+
+        def __contains__(self, key: object, /) -> bool: ...
+
+        def __len__(self, /) -> int:
+            """The number of `(key, value)` pairs, not the number of keys."""
+            ...
+
+        def __iter__(self, /) -> Iterator[tuple[bytes, bytes]]:
+            """Yields `(key, value)` pairs, a key will be yielded once per associated value.
+
+            So, if a key has 3 associated values, it will be yielded 3 times.
+            """
             ...
 
     @final
@@ -205,30 +408,107 @@ if TYPE_CHECKING:
             """
             ...
 
-        def channel_on(self, webview: Webview, /) -> "Channel":
-            """Gets a `Channel` for this channel ID on the given `Webview`."""
+        def channel_on(
+            self,
+            webview: Union[Webview, "WebviewWindow", "Window"],
+            /,
+            *,
+            max_queue: Optional[int] = None,
+        ) -> "Channel":
+            """Gets a `Channel` for this channel ID on the given `Webview`.
+
+            Args:
+                webview: The `Webview` that the JS channel belongs to.
+                    If it's a `Window`, the webview with the same label (i.e., the `WebviewWindow`)
+                    or the only webview of the window will be used.
+                max_queue: If set (must be positive), the data will be sent in order by a background task
+                    through a bounded queue of this size, see [Channel.send][pytauri.ffi.ipc.Channel.send].
+                    Otherwise, the data will be sent directly in `Channel.send`.
+            """
             ...
 
     @final
     class Channel:
         """[tauri::ipc::Channel](https://docs.rs/tauri/latest/tauri/ipc/struct.Channel.html)"""
 
+        @staticmethod
+        def new(
+            on_message: Callable[[Union[str, bytes]], object],
+            /,
+            *,
+            max_queue: Optional[int] = None,
+        ) -> "Channel":
+            """Creates a new Rust-side channel, the data sent to it will be passed to `on_message`.
+
+            `on_message` will be called with `str` (JSON) or `bytes` (raw) on the sender's thread
+            (while holding the GIL), so it must not block.
+            The exceptions raised by `on_message` are written to `sys.unraisablehook`.
+
+            This is useful to pass a channel to the Rust side (e.g., tauri plugins),
+            see `pytauri_core::ext_mod::ipc::Channel::channel`.
+            The channel is never closed, see [Channel.is_closed][pytauri.ffi.ipc.Channel.is_closed].
+
+            NOTE: the frontend (including the `eval`'d JavaScript) can only receive messages
+            from a channel, it can't send messages back to this one, so passing its ID to
+            the frontend is useless. Use a command to receive messages from the frontend instead.
+            """
+            ...
+
         def id(self, /) -> int:
             """The channel identifier."""
             ...
 
-        def send(self, data: _InvokeResponseBody, /) -> None:
+        def send(self, data: _InvokeResponseBody, /, *, block: bool = True) -> None:
             """Sends the given data through the channel.
 
             Args:
                 data: The data to send.
 
                     - If `str`, it will be deserialized as JSON on the frontend.
-                    - If `bytes`, it will be sent as `ArrayBuffer` to the frontend.
+                    - If `bytes` or other `Buffer`, it will be sent as `ArrayBuffer` to the frontend.
+                block: Only works for the channel with `max_queue`.
+                    If `True`, block (without holding the GIL) until there is free space in the queue;
+                    otherwise, raise `queue.Full` immediately.
+
+            Raises:
+                queue.Full: If the queue is full and `block` is `False`.
+                RuntimeError: If the channel with `max_queue` is closed.
+            """
+            ...
+
+        def send_many(
+            self, data: Iterable[_InvokeResponseBody], /, *, block: bool = True
+        ) -> None:
+            """Sends the given data in order, releasing the GIL only once.
+
+            If an error is raised, the data before it have been sent.
+            See [Channel.send][pytauri.ffi.ipc.Channel.send] for the arguments.
+            """
+            ...
+
+        def is_closed(self, /) -> bool:
+            """Whether the JS side of this channel is gone.
+
+            It's closed when the webview starts loading a new page,
+            or when the window of the webview is destroyed.
+            The data sent to a closed channel will be discarded by the frontend.
+            """
+            ...
+
+        def on_close(self, callback: Callable[[], object], /) -> None:
+            """Registers a callback that will be called once the channel is closed.
+
+            If the channel is already closed, the `callback` will be called immediately.
+            Otherwise, it will be called on another thread (while holding the GIL),
+            so it must not block, use `BlockingPortal` or `call_soon_threadsafe` to
+            notify your async code. The exceptions raised by `callback` are written to `sys.unraisablehook`.
+
+            See [Channel.is_closed][pytauri.ffi.ipc.Channel.is_closed] for when it's closed.
             """
             ...
 
 else:
+    Headers = _ipc_mod.Headers
     Invoke = _ipc_mod.Invoke
     InvokeResolver = _ipc_mod.InvokeResolver
     JavaScriptChannelId = _ipc_mod.JavaScriptChannelId
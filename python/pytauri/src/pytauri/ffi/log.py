@@ -0,0 +1,24 @@
+"""Bridges the Rust [log](https://docs.rs/log/latest/log/) records and the Python `logging` module."""
+
+from logging import LogRecord
+from typing import TYPE_CHECKING
+
+from pytauri.ffi._ext_mod import pytauri_mod
+
+__all__ = ["log_record"]
+
+_log_mod = pytauri_mod.log
+
+if TYPE_CHECKING:
+
+    def log_record(record: LogRecord, /) -> None:
+        """Forward the `record` to the Rust `log` crate.
+
+        The Rust target is the logger name with `.` replaced by `::`.
+        The records forwarded from Rust by `pyo3_utils::log::PyLogger`
+        will not be forwarded back.
+        """
+        ...
+
+else:
+    log_record = _log_mod.log_record
@@ -91,6 +91,17 @@ if TYPE_CHECKING:
             ...
 
         def label(self) -> str: ...
+        def builder_args(self) -> Optional["WebviewWindowBuilderArgs"]:
+            """Returns the kwargs this webview window was built with by `WebviewWindowBuilder`.
+
+            The aliases are resolved and the unset keys are omitted,
+            so it can be passed to `WebviewWindowBuilder` again, e.g., as a window preset.
+
+            Returns `None` if this webview window was not built by `WebviewWindowBuilder`
+            (e.g., it was created from the `tauri.conf.json`).
+            """
+            ...
+
         def on_window_event(
             self, handler: Callable[[WindowEventType], None], /
         ) -> None:
@@ -2,7 +2,7 @@
 
 import sys
 from collections import UserDict
-from collections.abc import Awaitable
+from collections.abc import Awaitable, Iterable
 from functools import cache, partial, wraps
 from inspect import Parameter, Signature, signature
 from logging import getLogger
@@ -54,6 +54,7 @@ from pytauri.ffi.lib import (
     _InvokeHandlerProto,  # pyright: ignore[reportPrivateUsage]
 )
 from pytauri.ffi.webview import Webview, WebviewWindow
+from pytauri.ffi.window import Window
 
 __all__ = [
     "ArgumentsType",
@@ -165,6 +166,21 @@ def _type_to_model(type_: Any) -> _ModelSerde[_T, RootModel[_T]]:
     return model_serde
 
 
+def _respond(resolver: InvokeResolver[Any], respond: Callable[[], None], /) -> None:
+    """Calls `respond`, ignores the error if the command has already been cancelled.
+
+    NOTE: don't check `resolver.cancelled` before responding, the command may be cancelled
+    (e.g., by its deadline) on another thread at any time.
+    """
+    try:
+        respond()
+    except RuntimeError:
+        # `cancelled` is guaranteed to be `True` if responding failed because of cancellation
+        if resolver.cancelled:
+            return
+        raise
+
+
 class Commands(UserDict[str, _PyInvokHandleData]):
     """This class provides features similar to [tauri::generate_handler](https://docs.rs/tauri/latest/tauri/macro.generate_handler.html).
 
@@ -211,7 +227,7 @@ class Commands(UserDict[str, _PyInvokHandleData]):
                     resp = await handler(**arguments, **states)
                     # TODO, PERF: idk if this will block?
                 except InvokeException as e:
-                    resolver.reject(e.value)
+                    _respond(resolver, lambda: resolver.reject(e.value))
                 except Exception as e:
                     # # TODO: Should we return the traceback to the frontend?
                     # # It might leak information.
@@ -221,9 +237,9 @@ class Commands(UserDict[str, _PyInvokHandleData]):
                         f"invoke_handler {handler}: `{handler.__name__}` raised an exception",
                         exc_info=e,
                     )
-                    resolver.reject(repr(e))
+                    _respond(resolver, lambda: resolver.reject(repr(e)))
                 else:
-                    resolver.resolve(resp)
+                    _respond(resolver, lambda: resolver.resolve(resp))
 
             except Exception as e:
                 msg = f"{_async_invoke_handler} implementation raised an exception, please report this as a pytauri bug"
@@ -457,6 +473,12 @@ class Commands(UserDict[str, _PyInvokHandleData]):
             "app_handle": AppHandle,
             "webview_window": WebviewWindow,
             "headers": Headers,
+            "webview": Webview,
+            "window": Window,
+            "url": str,
+            "origin": str,
+            "command_scope": dict,
+            "global_scope": dict,
         }
 
         for name, param in parameters.items():
@@ -728,9 +750,15 @@ class JavaScriptChannelId(
         ffi_js_channel_id = _FFIJavaScriptChannelId.from_str(value)
         return cls(ffi_js_channel_id)
 
-    def channel_on(self, webview: Webview, /) -> "Channel[_ModelTypeVar]":
+    def channel_on(
+        self,
+        webview: Union[Webview, WebviewWindow, Window],
+        /,
+        *,
+        max_queue: Optional[int] = None,
+    ) -> "Channel[_ModelTypeVar]":
         """See [pytauri.ffi.ipc.JavaScriptChannelId.channel_on][]."""
-        ffi_channel = self.root.channel_on(webview)
+        ffi_channel = self.root.channel_on(webview, max_queue=max_queue)
         return Channel(ffi_channel)
 
 
@@ -749,14 +777,39 @@ class Channel(Generic[_ModelTypeVar]):
     def __init__(self, ffi_channel: _FFIChannel, /):  # noqa: D107
         self._ffi_channel = ffi_channel
 
+    @classmethod
+    def new(
+        cls,
+        on_message: Callable[[Union[str, bytes]], object],
+        /,
+        *,
+        max_queue: Optional[int] = None,
+    ) -> Self:
+        """See [pytauri.ffi.ipc.Channel.new][]."""
+        return cls(_FFIChannel.new(on_message, max_queue=max_queue))
+
     def id(self, /) -> int:
         """See [pytauri.ffi.ipc.Channel.id][]."""
         return self._ffi_channel.id()
 
-    def send(self, data: _InvokeResponseBody, /) -> None:
+    def send(self, data: _InvokeResponseBody, /, *, block: bool = True) -> None:
         """See [pytauri.ffi.ipc.Channel.send][]."""
-        self._ffi_channel.send(data)
+        self._ffi_channel.send(data, block=block)
+
+    def send_many(
+        self, data: Iterable[_InvokeResponseBody], /, *, block: bool = True
+    ) -> None:
+        """See [pytauri.ffi.ipc.Channel.send_many][]."""
+        self._ffi_channel.send_many(data, block=block)
+
+    def is_closed(self, /) -> bool:
+        """See [pytauri.ffi.ipc.Channel.is_closed][]."""
+        return self._ffi_channel.is_closed()
+
+    def on_close(self, callback: Callable[[], object], /) -> None:
+        """See [pytauri.ffi.ipc.Channel.on_close][]."""
+        self._ffi_channel.on_close(callback)
 
-    def send_model(self, model: _ModelTypeVar, /) -> None:
-        """Equivalent to `self.send(model.model_dump_json())`."""
-        self.send(model.model_dump_json())
+    def send_model(self, model: _ModelTypeVar, /, *, block: bool = True) -> None:
+        """Equivalent to `self.send(model.model_dump_json(), block=block)`."""
+        self.send(model.model_dump_json(), block=block)
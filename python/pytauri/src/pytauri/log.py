@@ -0,0 +1,36 @@
+"""Bridges the Rust [log](https://docs.rs/log/latest/log/) records and the Python `logging` module.
+
+To forward the Rust records (including the ones emitted by pytauri) to Python `logging`,
+call [pyo3_utils::log::PyLogger::init](https://docs.rs/pyo3-utils/latest/pyo3_utils/log/struct.PyLogger.html)
+on the Rust side. The logger name is the Rust module path with `::` replaced by `.`.
+
+To forward the Python records to Rust, use [RustHandler][pytauri.log.RustHandler].
+"""
+
+from logging import Handler, LogRecord
+
+from pytauri.ffi.log import log_record
+
+__all__ = ["RustHandler"]
+
+
+class RustHandler(Handler):
+    """A `logging.Handler` that forwards the records to the Rust `log` crate.
+
+    # Examples
+
+    ```python
+    import logging
+
+    from pytauri.log import RustHandler
+
+    logging.getLogger().addHandler(RustHandler())
+    ```
+    """
+
+    def emit(self, record: LogRecord) -> None:
+        """Forward the `record` to Rust, see [pytauri.ffi.log.log_record][]."""
+        try:
+            log_record(record)
+        except Exception:
+            self.handleError(record)
@@ -1,10 +1,14 @@
 from collections.abc import Iterator
+from concurrent.futures import Future
 from contextlib import contextmanager
-from typing import Literal, Optional, cast
+from queue import Full
+from threading import Event as ThreadingEvent
+from time import perf_counter
+from typing import Callable, Literal, Optional, Union, cast
 
-from anyio import create_task_group
+from anyio import create_task_group, sleep
 from anyio.abc import TaskGroup
-from anyio.from_thread import start_blocking_portal
+from anyio.from_thread import BlockingPortal, start_blocking_portal
 from pydantic import BaseModel, ConfigDict, RootModel
 from pydantic.alias_generators import to_camel
 from pytauri import (
@@ -13,13 +17,24 @@ from pytauri import (
     Emitter,
     Event,
     Listener,
+    Manager,
+    WebviewUrl,
     builder_factory,
     context_factory,
 )
-from pytauri.ipc import Channel, JavaScriptChannelId
+from pytauri.ipc import Channel, Invoke, JavaScriptChannelId
 from pytauri.webview import WebviewWindow
 
-__all__ = ["app_handle_fixture"]
+__all__ = [
+    "app_handle_fixture",
+    "build_webview_window_with_platform_keys",
+    "check_webview_window_builder_args",
+    "check_webview_window_misspelled_key",
+    "check_channel_queue_full",
+    "closed_channels",
+    "invoke_handler",
+    "wait_invoke_handlers",
+]
 
 commands = Commands()
 
@@ -62,14 +77,199 @@ async def command(
     return "pong"
 
 
+class BenchBody(_BaseModel):
+    channel_id: JavaScriptChannelId[ChannelBody]
+    size: int
+    rounds: int
+
+
+BenchResult = RootModel[dict[str, float]]
+
+
+# NOTE: dont change the command name `bench_channel_send`,
+# it is used in the `test/ipc.rs`.
+@commands.command()
+async def bench_channel_send(
+    body: BenchBody, webview_window: WebviewWindow
+) -> BenchResult:
+    """Returns the average seconds of `Channel.send` for each kind of buffer."""
+    channel = body.channel_id.channel_on(webview_window.as_ref_webview())
+    buffer = bytearray(body.size)
+
+    def timeit(send: Callable[[], None]) -> float:
+        start = perf_counter()
+        for _ in range(body.rounds):
+            send()
+        return (perf_counter() - start) / body.rounds
+
+    return BenchResult(
+        {
+            # what we had to do before supporting the buffer protocol
+            "bytes(bytearray)": timeit(lambda: channel.send(bytes(buffer))),
+            "bytearray": timeit(lambda: channel.send(buffer)),
+            "memoryview": timeit(lambda: channel.send(memoryview(buffer))),
+        }
+    )
+
+
+class SleepBody(_BaseModel):
+    seconds: float
+
+
+# NOTE: dont change the command name `sleep`,
+# it is used in the `test/ipc.rs`.
+@commands.command("sleep")
+async def sleep_command(body: SleepBody) -> Literal["pong"]:
+    """Responds after `body.seconds`, used to test the deadline of the commands."""
+    await sleep(body.seconds)
+    return "pong"
+
+
+class OpenChannelBody(_BaseModel):
+    channel_id: JavaScriptChannelId[ChannelBody]
+    name: str
+
+
+_opened_channels: list[Channel[ChannelBody]] = []
+closed_channels: list[str] = []
+"""The `name`s of the channels opened by `open_channel`, in the order they were closed."""
+
+
+# NOTE: dont change the command name `open_channel`,
+# it is used in the `test/ipc.rs`.
+@commands.command()
+async def open_channel(body: OpenChannelBody, webview_window: WebviewWindow) -> None:
+    """Opens a channel on the webview, which appends `body.name` to `closed_channels` on close."""
+    channel = body.channel_id.channel_on(webview_window.as_ref_webview())
+    channel.on_close(lambda: closed_channels.append(body.name))
+    _opened_channels.append(channel)
+
+
+def check_channel_queue_full() -> None:
+    """Checks that `Channel.send(block=False)` raises `queue.Full` once `max_queue` is reached."""
+    started = ThreadingEvent()
+    release = ThreadingEvent()
+    finished = ThreadingEvent()
+    received: list[Union[str, bytes]] = []
+
+    def on_message(data: Union[str, bytes]) -> None:
+        started.set()
+        # blocks the worker of the queue
+        release.wait(10)
+        received.append(data)
+        if len(received) == 3:
+            finished.set()
+
+    channel = Channel[ChannelBody].new(on_message, max_queue=1)
+    channel.send("1")
+    assert started.wait(10), "the worker did not receive the data"
+    # the worker is blocked, so this one stays in the queue
+    channel.send("2", block=False)
+    try:
+        channel.send("3", block=False)
+    except Full:
+        pass
+    else:
+        raise AssertionError("`send(block=False)` should raise `queue.Full`")
+
+    release.set()
+    # blocks until the worker takes "2"
+    channel.send("4")
+    assert finished.wait(10), "the worker did not send all the data"
+    assert received == ["1", "2", "4"], received
+
+
+def build_webview_window_with_platform_keys(app_handle: AppHandle, label: str) -> None:
+    """Builds a webview window with the platform-specific keys of Windows and macOS.
+
+    The keys of the other platforms are ignored.
+    """
+    WebviewWindow(
+        app_handle,
+        label,
+        WebviewUrl.App("index.html"),
+        drag_and_drop=True,  # Windows only
+        hidden_title=True,  # macOS only
+    )
+
+
+def check_webview_window_misspelled_key(app_handle: AppHandle, label: str) -> None:
+    """Checks that a misspelled key is rejected with a suggestion."""
+    try:
+        WebviewWindow(
+            app_handle,
+            label,
+            WebviewUrl.App("index.html"),
+            titel="typo",  # pyright: ignore[reportCallIssue]
+        )
+    except TypeError as e:
+        assert "did you mean 'title'?" in str(e), e
+    else:
+        raise AssertionError("the misspelled key was accepted")
+
+
+def check_webview_window_builder_args(
+    app_handle: AppHandle, label: str, preset_label: str, rust_label: str
+) -> None:
+    """Checks that the builder args round-trip through `WebviewWindow.builder_args`.
+
+    `rust_label` is the label of a webview window built by Rust.
+    """
+    webview_window = WebviewWindow(
+        app_handle,
+        label,
+        WebviewUrl.App("index.html"),
+        title="preset",
+        inner_size=(800.0, 600.0),
+        resizable=False,
+    )
+    args = webview_window.builder_args()
+    assert args == {
+        "title": "preset",
+        "inner_size": (800.0, 600.0),
+        "resizable": False,
+    }, args
+
+    # build another webview window with the same args, i.e., as a window preset
+    preset = WebviewWindow(
+        app_handle, preset_label, WebviewUrl.App("index.html"), **args
+    )
+    assert preset.builder_args() == args
+
+    rust_webview_window = Manager.get_webview_window(app_handle, rust_label)
+    assert rust_webview_window is not None
+    assert rust_webview_window.builder_args() is None
+
+
 task_group: TaskGroup
+portal: BlockingPortal
+_invoke_handler_futures: list[Future[None]] = []
+
+
+def invoke_handler(invoke: Invoke, /) -> None:
+    """The `invoke_handler` of `commands`, for the apps built in `test/ipc.rs`.
+
+    It must be used within `app_handle_fixture`, see also `wait_invoke_handlers`.
+    """
+    _invoke_handler_futures.append(
+        portal.start_task_soon(
+            commands._async_invoke_handler,  # pyright: ignore[reportPrivateUsage]
+            invoke,
+        )
+    )
+
+
+def wait_invoke_handlers(timeout: float) -> None:
+    """Waits for the pending `invoke_handler` calls, and re-raises their exceptions."""
+    while _invoke_handler_futures:
+        _invoke_handler_futures.pop().result(timeout)
 
 
 # NOTE: dont change the func name `app_handle_fixture`,
 # it is used in the `test/ipc.rs`.
 @contextmanager
 def app_handle_fixture() -> Iterator[AppHandle]:
-    global task_group
+    global task_group, portal
     with (
         start_blocking_portal("asyncio") as portal,  # or `trio`
         portal.wrap_async_context_manager(portal.call(create_task_group)) as task_group,
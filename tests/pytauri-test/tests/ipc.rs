@@ -1,4 +1,14 @@
-use std::{collections::HashMap, env::var, error::Error, path::PathBuf, sync::LazyLock};
+use std::{
+    collections::HashMap,
+    env::var,
+    error::Error,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, LazyLock,
+    },
+    time::Duration,
+};
 
 use pyo3::{prelude::*, wrap_pymodule};
 use pytauri::standalone::{
@@ -10,9 +20,15 @@ use tauri::{
     ipc::{Channel, InvokeBody, InvokeResponseBody},
     test::{get_ipc_response, MockRuntime, INVOKE_KEY},
     webview::{InvokeRequest, Webview, WebviewWindowBuilder},
+    Manager as _,
 };
 
-use pytauri_test::test::{ext_mod, Runtime};
+use pytauri::ext_mod::{
+    ipc::{call_channel_on_close, close_channels, ChannelCloseEvent},
+    PyAppHandleExt as _,
+};
+use pytauri_test::test::{ext_mod, tauri_generate_context, Runtime};
+use tauri_plugin_pytauri::{replay, IpcMetricsExt as _, IpcRecord, PyInvokeHandlerExt as _};
 
 static PYI: LazyLock<PythonInterpreter> = LazyLock::new(|| {
     let virtual_env = var("VIRTUAL_ENV").unwrap();
@@ -57,11 +73,48 @@ where
     })
 }
 
+/// Builds another app with the customized plugin, whose `py_invoke_handler` is `pytauri_test.invoke_handler`.
+///
+/// It must be used within [app_handle_fixture], because the handler runs on its portal.
+fn mock_app(
+    builder: impl FnOnce(PyObject) -> tauri_plugin_pytauri::Builder,
+) -> PyResult<tauri::App<Runtime>> {
+    let invoke_handler = Python::with_gil(|py| {
+        PyResult::Ok(
+            py.import("pytauri_test")?
+                .getattr("invoke_handler")?
+                .unbind(),
+        )
+    })?;
+    let app = tauri::test::mock_builder()
+        .plugin(builder(invoke_handler).build())
+        .build(tauri_generate_context())
+        .unwrap();
+    Ok(app)
+}
+
+/// Waits for the `invoke_handler` calls of [mock_app], and re-raises their exceptions.
+fn wait_invoke_handlers() -> PyResult<()> {
+    Python::with_gil(|py| {
+        py.import("pytauri_test")?
+            .call_method1("wait_invoke_handlers", (10.0,))?;
+        Ok(())
+    })
+}
+
 pub fn get_pytauri_ipc_response<D: DeserializeOwned>(
     webview: &impl AsRef<Webview<MockRuntime>>,
     func_name: String,
     body: &impl Serialize,
 ) -> D {
+    try_get_pytauri_ipc_response(webview, func_name, body).unwrap()
+}
+
+pub fn try_get_pytauri_ipc_response<D: DeserializeOwned>(
+    webview: &impl AsRef<Webview<MockRuntime>>,
+    func_name: String,
+    body: &impl Serialize,
+) -> Result<D, serde_json::Value> {
     let mut headers = HashMap::new();
     headers.insert("pyfunc".to_string(), func_name);
     let headers = (&headers).try_into().unwrap();
@@ -89,15 +142,14 @@ pub fn get_pytauri_ipc_response<D: DeserializeOwned>(
             headers,
             invoke_key: INVOKE_KEY.to_string(),
         },
-    )
-    .unwrap();
+    )?;
 
     let data = match resp {
         InvokeResponseBody::Json(data) => data,
         InvokeResponseBody::Raw(_) => panic!("ipc should return Json `String`"),
     };
 
-    serde_json::from_str(&data).unwrap()
+    Ok(serde_json::from_str(&data).unwrap())
 }
 
 /// Test the command and channel IPC between Python and Frontend.
@@ -139,3 +191,374 @@ fn test_ipc() -> Result<(), Box<dyn Error>> {
     })?;
     Ok(())
 }
+
+/// Test that the remote pages can not call Python commands by default.
+#[test]
+fn test_origin_policy() -> Result<(), Box<dyn Error>> {
+    app_handle_fixture(|app| {
+        let webview = WebviewWindowBuilder::new(app, "main", Default::default())
+            .build()
+            .unwrap();
+
+        let channel = Channel::<()>::new(|_| Ok(()));
+        let body = json!(
+            {
+                "ping": "ping",
+                "channelId": channel
+            }
+        );
+
+        // NOTE: the url of the ipc request is always local (see `get_pytauri_ipc_response`),
+        // but the origin policy checks the current url of the webview.
+        webview
+            .navigate("https://example.com/".parse().unwrap())
+            .unwrap();
+        let err = try_get_pytauri_ipc_response::<String>(&webview, "command".into(), &body)
+            .expect_err("remote page should be rejected");
+        assert!(
+            err.as_str().unwrap().contains("https://example.com/"),
+            "unexpected rejection: {err}"
+        );
+
+        webview
+            .navigate("tauri://localhost/".parse().unwrap())
+            .unwrap();
+        let resp: String = get_pytauri_ipc_response(&webview, "command".into(), &body);
+        assert_eq!(resp, "pong");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// Test that the windows can have their own `py_invoke_handler`.
+#[test]
+fn test_labeled_py_invoke_handler() -> Result<(), Box<dyn Error>> {
+    app_handle_fixture(|app| {
+        let main = WebviewWindowBuilder::new(app, "main", Default::default())
+            .build()
+            .unwrap();
+        let preview = WebviewWindowBuilder::new(app, "preview-1", Default::default())
+            .build()
+            .unwrap();
+
+        let channel = Channel::<()>::new(|_| Ok(()));
+        let body = json!(
+            {
+                "ping": "ping",
+                "channelId": channel
+            }
+        );
+
+        let handler = Python::with_gil(|py| {
+            py.eval(c"lambda invoke: invoke.reject('preview')", None, None)
+                .map(Bound::unbind)
+        })?;
+        let expected = Python::with_gil(|py| handler.clone_ref(py));
+        app.add_py_invoke_handler("preview-*", handler).unwrap();
+        assert!(app
+            .try_py_invoke_handler_for("preview-1")
+            .unwrap()
+            .is(&expected));
+        assert!(!app.try_py_invoke_handler_for("main").unwrap().is(&expected));
+
+        let err = try_get_pytauri_ipc_response::<String>(&preview, "command".into(), &body)
+            .expect_err("should be handled by the preview handler");
+        assert_eq!(err, "preview");
+        let resp: String = get_pytauri_ipc_response(&main, "command".into(), &body);
+        assert_eq!(resp, "pong");
+
+        assert!(app.remove_py_invoke_handler("preview-*").unwrap());
+        let resp: String = get_pytauri_ipc_response(&preview, "command".into(), &body);
+        assert_eq!(resp, "pong");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// Test that the Rust commands are dispatched without the GIL,
+/// after the origin and ACL checks, and take precedence over the Python commands.
+/// They are also counted in the metrics and recorded like the Python commands.
+#[test]
+fn test_rust_command() -> Result<(), Box<dyn Error>> {
+    app_handle_fixture(|_app| {
+        let record_path = std::env::temp_dir().join(format!(
+            "pytauri-test-rust-command-{}.jsonl",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&record_path);
+
+        let called = Arc::new(AtomicUsize::new(0));
+        let app = mock_app(|handler| {
+            let called = called.clone();
+            // NOTE: `command` is also a Python command
+            tauri_plugin_pytauri::Builder::new(handler)
+                .record_ipc(&record_path)
+                .rust_command("command", move |_message| {
+                    called.fetch_add(1, Ordering::SeqCst);
+                    // SAFETY: it only checks the GIL state of the current thread
+                    let gil_held = unsafe { pyo3::ffi::PyGILState_Check() } != 0;
+                    async move { Ok(if gil_held { "gil" } else { "rust" }) }
+                })
+        })?;
+        let body = json!({ "ping": "ping" });
+
+        let main = WebviewWindowBuilder::new(&app, "main", Default::default())
+            .build()
+            .unwrap();
+        let resp: String = get_pytauri_ipc_response(&main, "command".into(), &body);
+        assert_eq!(resp, "rust");
+        assert_eq!(called.load(Ordering::SeqCst), 1);
+
+        let metrics = &app.ipc_metrics()["command"];
+        assert_eq!((metrics.invoked, metrics.resolved), (1, 1));
+        assert_eq!(metrics.gil_wait, Duration::ZERO);
+
+        // the recording is written on a background thread
+        let mut records = Vec::new();
+        for _ in 0..100 {
+            records = IpcRecord::read_jsonl(&record_path).unwrap_or_default();
+            if !records.is_empty() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].command, "command");
+        let mismatches = replay(&app, &records);
+        assert!(mismatches.is_empty(), "{mismatches:?}");
+        assert_eq!(called.load(Ordering::SeqCst), 2);
+
+        // the origin policy
+        main.navigate("https://example.com/".parse().unwrap())
+            .unwrap();
+        try_get_pytauri_ipc_response::<String>(&main, "command".into(), &body)
+            .expect_err("remote page should be rejected");
+        assert_eq!(called.load(Ordering::SeqCst), 2);
+
+        // the ACL, see `capabilities/default.json`
+        let untrusted = WebviewWindowBuilder::new(&app, "untrusted", Default::default())
+            .build()
+            .unwrap();
+        try_get_pytauri_ipc_response::<String>(&untrusted, "command".into(), &body)
+            .expect_err("the window without capability should be rejected");
+        assert_eq!(called.load(Ordering::SeqCst), 2);
+        assert_eq!(app.ipc_metrics()["command"].denied, 2);
+
+        let _ = std::fs::remove_file(&record_path);
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// Test that the deadline firing while the handler is running does not raise in the handler.
+#[test]
+fn test_deadline_while_handler_running() -> Result<(), Box<dyn Error>> {
+    app_handle_fixture(|_app| {
+        let app = mock_app(|handler| {
+            tauri_plugin_pytauri::Builder::new(handler)
+                .command_timeout("sleep", Duration::from_millis(50))
+        })?;
+        let webview = WebviewWindowBuilder::new(&app, "main", Default::default())
+            .build()
+            .unwrap();
+
+        let err = try_get_pytauri_ipc_response::<String>(
+            &webview,
+            "sleep".into(),
+            &json!({ "seconds": 0.5 }),
+        )
+        .expect_err("should be rejected by the deadline");
+        assert!(
+            err.as_str().unwrap().contains("did not respond within"),
+            "unexpected rejection: {err}"
+        );
+
+        // the handler responds after the deadline, which must not raise
+        wait_invoke_handlers()?;
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// Gets `pytauri_test.closed_channels`.
+fn closed_channels() -> PyResult<Vec<String>> {
+    Python::with_gil(|py| {
+        py.import("pytauri_test")?
+            .getattr("closed_channels")?
+            .extract()
+    })
+}
+
+/// Test that the channels are closed when the page reloads or the window is destroyed,
+/// and their `on_close` callbacks are called exactly once.
+//
+// NOTE: [MockRuntime] doesn't emit the page load and window events,
+// so we call [close_channels] as the plugin does.
+#[test]
+fn test_channel_lifecycle() -> Result<(), Box<dyn Error>> {
+    app_handle_fixture(|app| {
+        let webview = WebviewWindowBuilder::new(app, "main", Default::default())
+            .build()
+            .unwrap();
+
+        let open_channel = |name: &str| {
+            let channel = Channel::<()>::new(|_| Ok(()));
+            let body = json!({ "channelId": channel, "name": name });
+            let () = get_pytauri_ipc_response(&webview, "open_channel".into(), &body);
+        };
+        let close = |event| -> PyResult<usize> {
+            let callbacks = close_channels(app, event);
+            let len = callbacks.len();
+            Python::with_gil(|py| call_channel_on_close(py, callbacks));
+            Ok(len)
+        };
+
+        open_channel("page");
+        // the other webviews are not affected
+        assert_eq!(close(ChannelCloseEvent::PageLoad("other"))?, 0);
+        assert_eq!(close(ChannelCloseEvent::PageLoad("main"))?, 1);
+        assert_eq!(closed_channels()?, ["page"]);
+        // already closed
+        assert_eq!(close(ChannelCloseEvent::PageLoad("main"))?, 0);
+        assert_eq!(closed_channels()?, ["page"]);
+
+        open_channel("window");
+        assert_eq!(close(ChannelCloseEvent::WindowDestroyed("other"))?, 0);
+        assert_eq!(close(ChannelCloseEvent::WindowDestroyed("main"))?, 1);
+        assert_eq!(close(ChannelCloseEvent::WindowDestroyed("main"))?, 0);
+        assert_eq!(closed_channels()?, ["page", "window"]);
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// Test that `Channel.send(block=False)` raises `queue.Full` once `max_queue` is reached.
+#[test]
+fn test_channel_queue_full() -> Result<(), Box<dyn Error>> {
+    app_handle_fixture(|_app| {
+        Python::with_gil(|py| {
+            py.import("pytauri_test")?
+                .call_method0("check_channel_queue_full")?;
+            Ok(())
+        })
+    })?;
+    Ok(())
+}
+
+/// Test that the keys of the `cfg`-disabled fields (e.g., of the other platforms)
+/// are accepted by `WebviewWindowBuilderArgs`, but the misspelled keys are still rejected.
+#[test]
+fn test_platform_specific_builder_args() -> Result<(), Box<dyn Error>> {
+    app_handle_fixture(|app| {
+        Python::with_gil(|py| {
+            let pytauri_test = py.import("pytauri_test")?;
+            let py_app_handle = app.py_app_handle().clone_ref(py);
+            pytauri_test.call_method1(
+                "build_webview_window_with_platform_keys",
+                (&py_app_handle, "platform"),
+            )?;
+            pytauri_test.call_method1(
+                "check_webview_window_misspelled_key",
+                (&py_app_handle, "misspelled"),
+            )?;
+            PyResult::Ok(())
+        })?;
+        assert!(app.get_webview_window("platform").is_some());
+        assert!(app.get_webview_window("misspelled").is_none());
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// Test that `WebviewWindowBuilderArgs` round-trips through `WebviewWindow.builder_args`.
+#[test]
+fn test_webview_window_builder_args() -> Result<(), Box<dyn Error>> {
+    app_handle_fixture(|app| {
+        let _webview = WebviewWindowBuilder::new(app, "rust", Default::default())
+            .build()
+            .unwrap();
+        Python::with_gil(|py| {
+            py.import("pytauri_test")?.call_method1(
+                "check_webview_window_builder_args",
+                (app.py_app_handle().clone_ref(py), "args", "preset", "rust"),
+            )?;
+            PyResult::Ok(())
+        })?;
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// Test that the ipc recordings can be replayed and diffed.
+#[test]
+fn test_ipc_replay() -> Result<(), Box<dyn Error>> {
+    app_handle_fixture(|app| {
+        let _webview = WebviewWindowBuilder::new(app, "main", Default::default())
+            .build()
+            .unwrap();
+
+        let channel = Channel::<()>::new(|_| Ok(()));
+        let body = serde_json::to_vec(&json!({ "ping": "ping", "channelId": channel })).unwrap();
+        let record = |response| -> IpcRecord {
+            serde_json::from_value(json!({
+                "webview": "main",
+                "cmd": "pyfunc",
+                "command": "command",
+                "headers": [["pyfunc", "command"]],
+                "body": { "raw": body },
+                "response": response,
+            }))
+            .unwrap()
+        };
+
+        let expected = record(json!({ "resolved": { "json": "pong" } }));
+        let unexpected = record(json!({ "resolved": { "json": "ping" } }));
+
+        let mismatches = replay(app, [&expected, &unexpected]);
+        assert_eq!(mismatches.len(), 1, "{mismatches:?}");
+        assert_eq!(mismatches[0].index, 1);
+        assert_eq!(mismatches[0].actual, Some(expected.response));
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// Benchmark `Channel.send` with the buffer protocol (zero-copy in Python) vs `bytes`.
+///
+/// Run it with `cargo test -p pytauri-test --features test --release -- --ignored --nocapture bench_`.
+#[test]
+#[ignore = "benchmark"]
+fn bench_channel_send() -> Result<(), Box<dyn Error>> {
+    app_handle_fixture(|app| {
+        let webview = WebviewWindowBuilder::new(app, "main", Default::default())
+            .build()
+            .unwrap();
+
+        let channel = Channel::<()>::new(|_| Ok(()));
+
+        for size in [1024, 1024 * 1024, 64 * 1024 * 1024] {
+            let body = json!(
+                {
+                    "channelId": channel,
+                    "size": size,
+                    "rounds": 20
+                }
+            );
+            let resp: HashMap<String, f64> =
+                get_pytauri_ipc_response(&webview, "bench_channel_send".into(), &body);
+
+            let mut resp = resp.into_iter().collect::<Vec<_>>();
+            resp.sort_by(|(a, _), (b, _)| a.cmp(b));
+            for (kind, secs) in resp {
+                println!("size: {size:>10} B, {kind:>16}: {:>10.3} us", secs * 1e6);
+            }
+        }
+
+        Ok(())
+    })?;
+    Ok(())
+}